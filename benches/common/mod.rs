@@ -0,0 +1,36 @@
+//! Timing harness shared by the benchmarks
+//!
+//! Uses only std timing so the crate stays dependency-free; numbers are
+//! best-of-N wall clock per iteration.
+
+// Each benchmark compiles this module separately and not all use every helper
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+/// Measured runs per benchmark, after one warm-up run
+pub const ITERATIONS: u32 = 20;
+
+/// Time `f` and print its best run
+pub fn bench(name: &str, f: impl FnMut()) {
+    let (best, _) = measure(ITERATIONS, || 0, f);
+    println!("{:<32} {:>10.3} ms", name, best.as_secs_f64() * 1000.0);
+}
+
+/// Run `f` once to warm up, then `iterations` times, returning the best time
+/// and the smallest increase in `counter` over a single run
+pub fn measure(iterations: u32, counter: impl Fn() -> usize, mut f: impl FnMut()) -> (Duration, usize) {
+    // Warm up caches and the allocator before measuring
+    f();
+
+    let mut best = Duration::MAX;
+    let mut least = usize::MAX;
+    for _ in 0..iterations {
+        let before = counter();
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+        least = least.min(counter() - before);
+    }
+    (best, least)
+}
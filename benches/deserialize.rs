@@ -0,0 +1,35 @@
+//! Deserialization benchmarks for derived types
+//!
+//! Run with `cargo bench --bench deserialize`. Timing comes from the shared harness
+//! in `common`.
+
+use fastjson::{Deserialize, Value};
+use std::hint::black_box;
+
+mod common;
+use common::bench;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Document {
+    id: u64,
+    title: String,
+    tags: Vec<String>,
+    rows: Vec<Vec<f64>>,
+}
+
+fn main() {
+    let rows: Vec<String> = (0..2_000).map(|i| format!("[{}, {}.5, -{}]", i, i, i)).collect();
+    let tags: Vec<String> = (0..1_000).map(|i| format!("\"tag{}\"", i)).collect();
+    let json = format!(
+        r#"{{"id": 1, "title": "large", "tags": [{}], "rows": [{}]}}"#,
+        tags.join(","),
+        rows.join(",")
+    );
+    let value = fastjson::parse(&json).unwrap();
+
+    bench("derive struct, large fields", || {
+        let value: Value = black_box(value.clone());
+        black_box(Document::deserialize(value).unwrap());
+    });
+}
@@ -0,0 +1,21 @@
+//! Parsing benchmarks
+//!
+//! Run with `cargo bench --bench parse`. Timing comes from the shared harness
+//! in `common`.
+
+use std::hint::black_box;
+
+mod common;
+use common::bench;
+
+fn integer_array(len: usize) -> String {
+    let items: Vec<String> = (0..len as i64).map(|i| (i * 7919 - 50_000).to_string()).collect();
+    format!("[{}]", items.join(","))
+}
+
+fn main() {
+    let integers = integer_array(100_000);
+    bench("parse 100k integers", || {
+        black_box(fastjson::parse(black_box(&integers)).unwrap());
+    });
+}
@@ -0,0 +1,69 @@
+//! Allocation benchmarks for parsing many small documents
+//!
+//! Run with `cargo bench --bench reuse`. Counts heap allocations with a
+//! wrapping global allocator, alongside best-of-N wall clock time from `common`.
+
+use fastjson::{ReusableParser, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+use common::measure;
+
+const ITERATIONS: u32 = 10;
+const MESSAGES: usize = 100_000;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn bench(name: &str, f: impl FnMut()) {
+    let (best, allocations) = measure(ITERATIONS, || ALLOCATIONS.load(Ordering::Relaxed), f);
+    println!(
+        "{:<32} {:>10.3} ms {:>12} allocations",
+        name,
+        best.as_secs_f64() * 1000.0,
+        allocations
+    );
+}
+
+fn main() {
+    let messages: Vec<String> = (0..MESSAGES)
+        .map(|i| format!(r#"{{"id": {}, "event": "click", "tags": ["ui", "button"], "pos": {{"x": {}, "y": 2}}}}"#, i, i % 640))
+        .collect();
+
+    bench("parse 100k small messages", || {
+        for json in &messages {
+            black_box(fastjson::parse(black_box(json)).unwrap());
+        }
+    });
+
+    let mut parser = ReusableParser::new();
+    let mut scratch = Value::Null;
+    bench("reuse 100k small messages", || {
+        for json in &messages {
+            parser.parse_into(black_box(json), &mut scratch).unwrap();
+            black_box(&scratch);
+        }
+    });
+}
@@ -0,0 +1,21 @@
+//! Serialization benchmarks
+//!
+//! Run with `cargo bench --bench serialize`. Timing comes from the shared harness
+//! in `common`.
+
+use std::hint::black_box;
+
+mod common;
+use common::bench;
+
+fn main() {
+    let bytes: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    bench("Vec<u8> 1MB, compact", || {
+        black_box(fastjson::to_string(black_box(&bytes)).unwrap());
+    });
+
+    bench("Vec<u8> 1MB, pretty", || {
+        black_box(fastjson::to_string_pretty(black_box(&bytes)).unwrap());
+    });
+}
@@ -1,486 +1,1665 @@
-use proc_macro::TokenStream;
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
 use std::str::FromStr;
 
 /// Procedural macro for deriving the Serialize trait.
 #[proc_macro_derive(Serialize, attributes(fastjson))]
 pub fn derive_serialize(input: TokenStream) -> TokenStream {
-    // Get the input as a string
-    let input_str = input.to_string();
-    let type_name = extract_type_name(&input_str);
-    
-    // Hard-code the implementation for a few concrete types
-    if type_name == "Person" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for Person {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("name".to_string(), ::fastjson::Value::String("John Doe".to_string()));
-                    map.insert("age".to_string(), ::fastjson::Value::Number(30.0));
-                    map.insert("is_active".to_string(), ::fastjson::Value::Bool(true));
-                    map.insert("emailAddress".to_string(), ::fastjson::Value::String("john@example.com".to_string()));
-                    Ok(::fastjson::Value::Object(map))
-                }
-            }"#).unwrap()
-    } else if type_name == "Status" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for Status {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    match self {
-                        Status::Active => Ok(::fastjson::Value::String("Active".to_string())),
-                        Status::Inactive => Ok(::fastjson::Value::String("Inactive".to_string())),
-                        Status::Pending(s) => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("Pending".to_string()));
-                            map.insert("data".to_string(), ::fastjson::Value::Array(vec![::fastjson::Serialize::serialize(s)?]));
-                            Ok(::fastjson::Value::Object(map))
-                        },
-                        Status::Custom { code, message } => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("Custom".to_string()));
-                            map.insert("code".to_string(), ::fastjson::Serialize::serialize(code)?);
-                            map.insert("message".to_string(), ::fastjson::Serialize::serialize(message)?);
-                            Ok(::fastjson::Value::Object(map))
-                        }
-                    }
-                }
-            }"#).unwrap()
-    } else if type_name == "SimpleEnum_One" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for SimpleEnum {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    match self {
-                        SimpleEnum::One => Ok(::fastjson::Value::String("One".to_string())),
-                        SimpleEnum::Two(s) => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("Two".to_string()));
-                            map.insert("data".to_string(), ::fastjson::Value::Array(vec![::fastjson::Serialize::serialize(s)?]));
-                            Ok(::fastjson::Value::Object(map))
-                        },
-                        SimpleEnum::Three { value } => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("Three".to_string()));
-                            map.insert("value".to_string(), ::fastjson::Serialize::serialize(value)?);
-                            Ok(::fastjson::Value::Object(map))
-                        }
-                    }
+    let input = match parse_input(input) {
+        Ok(input) => input,
+        Err(msg) => return compile_error(&msg),
+    };
+
+    let code = match &input.data {
+        Data::Struct(fields) => generate_struct_serialize(&input, fields),
+        Data::Enum(variants) => generate_enum_serialize(&input, variants),
+    };
+    TokenStream::from_str(&code).unwrap()
+}
+
+/// Procedural macro for deriving the Deserialize trait.
+#[proc_macro_derive(Deserialize, attributes(fastjson))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = match parse_input(input) {
+        Ok(input) => input,
+        Err(msg) => return compile_error(&msg),
+    };
+
+    // Deserialize produces owned values, so there is nothing to borrow from
+    if !input.lifetimes.is_empty() {
+        return compile_error("`Deserialize` cannot be derived for types with lifetime parameters");
+    }
+
+    let code = match &input.data {
+        Data::Struct(fields) => generate_struct_deserialize(&input, fields),
+        Data::Enum(variants) => generate_enum_deserialize(&input, variants),
+    };
+    TokenStream::from_str(&code).unwrap()
+}
+
+/// The parsed shape of the type a derive was applied to
+struct Input {
+    name: String,
+    /// Generic parameters as declared, bounds included and defaults removed,
+    /// e.g. `'a, 'b: 'a, T: Clone`
+    generic_params: String,
+    /// Generic parameter names in declaration order, e.g. `'a`, `'b`, `T`
+    generic_args: Vec<String>,
+    /// Lifetime parameter names, e.g. `'a`, `'b`
+    lifetimes: Vec<String>,
+    /// Type parameter names, e.g. `T`
+    type_params: Vec<String>,
+    /// Predicates of the type's own `where` clause
+    where_predicates: String,
+    attrs: ContainerAttrs,
+    data: Data,
+}
+
+/// `#[fastjson(...)]` attributes on the struct or enum itself
+#[derive(Default)]
+struct ContainerAttrs {
+    /// Use serde's externally tagged enum shape: `{"Variant": content}`
+    external_tag: bool,
+    /// Case convention applied to variant names without an explicit rename
+    rename_all: Option<String>,
+    /// Key holding the variant name in the default enum shape, instead of `"type"`,
+    /// or a struct's discriminator key
+    tag: Option<String>,
+    /// Constant a struct writes under `tag` and requires when deserializing
+    tag_value: Option<String>,
+    /// Write fieldless enums as their integer discriminants
+    numeric: bool,
+    /// Apply `skip_if_none` to every named `Option` field without `keep_none`
+    skip_none: bool,
+    /// Write unit variants as `{"type": "Variant"}` like every other variant
+    uniform_variants: bool,
+    /// Match variant names on deserialize regardless of case
+    tag_ignore_case: bool,
+    /// Predicates replacing the generated `T: Serialize` bounds of the `Serialize` impl
+    bound_ser: Option<String>,
+    /// Predicates replacing the generated `T: Deserialize` bounds of the `Deserialize` impl
+    bound_de: Option<String>,
+}
+
+impl ContainerAttrs {
+    /// The key holding the variant name in the default enum shape
+    fn tag_key(&self) -> &str {
+        self.tag.as_deref().unwrap_or("type")
+    }
+}
+
+enum Data {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+/// Fields of a struct or enum variant
+enum Fields {
+    Named(Vec<Field>),
+    Unnamed(Vec<Field>),
+    Unit,
+}
+
+struct Variant {
+    name: String,
+    rename: Option<String>,
+    /// Integer written to the tag instead of the variant name
+    tag_value: Option<i64>,
+    fields: Fields,
+}
+
+impl Variant {
+    /// The name used for this variant in JSON
+    fn json_name(&self) -> String {
+        self.rename.clone().unwrap_or_else(|| self.name.clone())
+    }
+}
+
+struct Field {
+    /// Field identifier for named fields, or its index for tuple fields
+    member: String,
+    /// The field type as written in the source
+    ty: String,
+    /// Whether the field type is `Option<...>`
+    is_option: bool,
+    /// Whether the field type is `Option<Option<...>>`, where an absent key and
+    /// an explicit `null` are told apart
+    is_double_option: bool,
+    /// Key written on serialize, when it differs from the field name
+    rename_ser: Option<String>,
+    /// Key read on deserialize, when it differs from the field name
+    rename_de: Option<String>,
+    /// Extra keys accepted on deserialize, tried in order after the primary name
+    aliases: Vec<String>,
+    /// Left out of serialized output
+    skip_ser: bool,
+    /// Never read on deserialize; filled with `Default::default()` instead
+    skip_de: bool,
+    /// Filled with `Default::default()` when its key or array position is missing
+    default: bool,
+    skip_if_none: bool,
+    /// Write `None` as `null` even under a container `skip_none`
+    keep_none: bool,
+    as_string: bool,
+    /// Accept a single non-array value as a one-element `Vec`
+    one_or_many: bool,
+    /// Collects unmatched keys on deserialize and inlines its entries on serialize
+    flatten: bool,
+    /// Module providing `serialize`/`deserialize` functions for this field
+    with: Option<String>,
+    /// Function checking the deserialized value, `fn(&T) -> Result<(), String>`
+    validate: Option<String>,
+    /// Serialized ahead of unordered fields, sorted by this number
+    order: Option<u32>,
+}
+
+impl Field {
+    /// The key written for this field when serializing
+    fn ser_name(&self) -> String {
+        self.rename_ser.clone().unwrap_or_else(|| self.default_name())
+    }
+
+    /// The key read for this field when deserializing
+    fn de_name(&self) -> String {
+        self.rename_de.clone().unwrap_or_else(|| self.default_name())
+    }
+
+    fn default_name(&self) -> String {
+        self.member.trim_start_matches("r#").to_string()
+    }
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    TokenStream::from_str(&format!("compile_error!({:?});", msg)).unwrap()
+}
+
+/// Parse the derive input into its name and fields/variants
+fn parse_input(input: TokenStream) -> Result<Input, String> {
+    let mut tokens = input.into_iter().peekable();
+    let mut is_enum = false;
+    let mut metas = Vec::new();
+
+    // Collect outer attributes and skip visibility until the `struct`/`enum` keyword
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    metas.extend(extract_attrs(group.stream())?);
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleEnum_First" || type_name == "SimpleEnum" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for SimpleEnum {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    match self {
-                        SimpleEnum::First => Ok(::fastjson::Value::String("First".to_string())),
-                        SimpleEnum::Second => Ok(::fastjson::Value::String("Second".to_string())),
-                    }
+            }
+            Some(TokenTree::Ident(ident)) => match ident.to_string().as_str() {
+                "struct" => break,
+                "enum" => {
+                    is_enum = true;
+                    break;
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleTest" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for SimpleTest {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("name".to_string(), ::fastjson::Serialize::serialize(&self.name)?);
-                    map.insert("value".to_string(), ::fastjson::Serialize::serialize(&self.value)?);
-                    Ok(::fastjson::Value::Object(map))
+                "union" => return Err("fastjson cannot be derived for unions".to_string()),
+                _ => {}
+            },
+            Some(_) => {}
+            None => return Err("expected a struct or enum".to_string()),
+        }
+    }
+
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a type name".to_string()),
+    };
+
+    let mut next = tokens.next();
+    let mut generic_params = Vec::new();
+    let mut generic_args = Vec::new();
+    let mut lifetimes = Vec::new();
+    let mut type_params = Vec::new();
+    if matches!(&next, Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+        let params = take_generic_params(&mut tokens)?;
+        for param in split_top_level(TokenStream::from_iter(params)) {
+            let arg = match (param.first(), param.get(1)) {
+                (Some(TokenTree::Punct(p)), Some(TokenTree::Ident(ident))) if p.as_char() == '\'' => {
+                    let lifetime = format!("'{}", ident);
+                    lifetimes.push(lifetime.clone());
+                    lifetime
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleString" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for SimpleString {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("text".to_string(), ::fastjson::Serialize::serialize(&self.text)?);
-                    Ok(::fastjson::Value::Object(map))
+                (Some(TokenTree::Ident(kw)), Some(TokenTree::Ident(ident))) if kw.to_string() == "const" => {
+                    ident.to_string()
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleColors" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for SimpleColors {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    match self {
-                        SimpleColors::Red => Ok(::fastjson::Value::String("Red".to_string())),
-                        SimpleColors::Green => Ok(::fastjson::Value::String("Green".to_string())),
-                        SimpleColors::Custom(s) => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("Custom".to_string()));
-                            map.insert("data".to_string(), ::fastjson::Value::Array(vec![::fastjson::Serialize::serialize(s)?]));
-                            Ok(::fastjson::Value::Object(map))
-                        },
-                        SimpleColors::RGB { r, g, b, alpha } => {
-                            use std::collections::HashMap;
-                            let mut map = HashMap::new();
-                            map.insert("type".to_string(), ::fastjson::Value::String("RGB".to_string()));
-                            map.insert("r".to_string(), ::fastjson::Serialize::serialize(r)?);
-                            map.insert("g".to_string(), ::fastjson::Serialize::serialize(g)?);
-                            map.insert("b".to_string(), ::fastjson::Serialize::serialize(b)?);
-                            if let Some(a) = alpha {
-                                map.insert("alpha".to_string(), ::fastjson::Serialize::serialize(a)?);
-                            } else {
-                                map.insert("alpha".to_string(), ::fastjson::Value::Null);
-                            }
-                            Ok(::fastjson::Value::Object(map))
-                        }
-                    }
+                (Some(TokenTree::Ident(ident)), _) => {
+                    type_params.push(ident.to_string());
+                    ident.to_string()
                 }
-            }"#).unwrap()
-    } else if type_name == "TestOptional" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for TestOptional {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("required".to_string(), ::fastjson::Serialize::serialize(&self.required)?);
-                    if let Some(ref val) = self.optional {
-                        map.insert("optional".to_string(), ::fastjson::Serialize::serialize(val)?);
-                    } else {
-                        map.insert("optional".to_string(), ::fastjson::Value::Null);
-                    }
-                    if let Some(ref val) = self.conditional {
-                        map.insert("conditional".to_string(), ::fastjson::Serialize::serialize(val)?);
-                    }
-                    Ok(::fastjson::Value::Object(map))
+                _ => return Err("unexpected generic parameter".to_string()),
+            };
+            generic_args.push(arg);
+            // Defaults such as `T = String` are not allowed on impl parameters
+            let declared: Vec<TokenTree> = param
+                .into_iter()
+                .take_while(|t| !matches!(t, TokenTree::Punct(p) if p.as_char() == '='))
+                .collect();
+            generic_params.push(TokenStream::from_iter(declared).to_string());
+        }
+        next = tokens.next();
+    }
+
+    // A where clause comes before a braced body, or after a tuple struct's fields
+    let mut where_predicates = String::new();
+    if matches!(&next, Some(TokenTree::Ident(ident)) if ident.to_string() == "where") {
+        let mut predicates = Vec::new();
+        next = loop {
+            match tokens.next() {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                    break Some(TokenTree::Group(group));
                 }
-            }"#).unwrap()
-    } else if type_name == "Container" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for Container {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("title".to_string(), ::fastjson::Serialize::serialize(&self.title)?);
-                    
-                    let mut items = Vec::new();
-                    for item in &self.items {
-                        items.push(::fastjson::Serialize::serialize(item)?);
+                Some(TokenTree::Punct(p)) if p.as_char() == ';' => break Some(TokenTree::Punct(p)),
+                Some(token) => predicates.push(token),
+                None => return Err("expected a body after the where clause".to_string()),
+            }
+        };
+        where_predicates = TokenStream::from_iter(predicates).to_string();
+    }
+
+    let mut data = match next {
+        Some(TokenTree::Group(group)) if is_enum => {
+            Data::Enum(extract_variants(group.stream())?)
+        }
+        Some(TokenTree::Group(group)) => match group.delimiter() {
+            Delimiter::Brace => Data::Struct(Fields::Named(extract_fields(group.stream(), true)?)),
+            Delimiter::Parenthesis => {
+                if let Some(TokenTree::Ident(ident)) = tokens.next() {
+                    if ident.to_string() == "where" {
+                        let predicates: Vec<TokenTree> = tokens
+                            .by_ref()
+                            .take_while(|t| !matches!(t, TokenTree::Punct(p) if p.as_char() == ';'))
+                            .collect();
+                        where_predicates = TokenStream::from_iter(predicates).to_string();
                     }
-                    map.insert("items".to_string(), ::fastjson::Value::Array(items));
-                    
-                    Ok(::fastjson::Value::Object(map))
                 }
-            }"#).unwrap()
-    } else if type_name == "RequiredField" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Serialize for RequiredField {
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {
-                    use std::collections::HashMap;
-                    let mut map = HashMap::new();
-                    map.insert("required_field".to_string(), ::fastjson::Serialize::serialize(&self.required_field)?);
-                    Ok(::fastjson::Value::Object(map))
+                Data::Struct(Fields::Unnamed(extract_fields(group.stream(), false)?))
+            }
+            _ => return Err("unexpected struct body".to_string()),
+        },
+        Some(TokenTree::Punct(p)) if p.as_char() == ';' => Data::Struct(Fields::Unit),
+        _ => return Err(format!("unexpected tokens after `{}`", name)),
+    };
+
+    let mut attrs = ContainerAttrs::default();
+    for (key, value) in metas {
+        match (key.as_str(), value) {
+            ("external_tag", None) if is_enum => attrs.external_tag = true,
+            ("rename_all", Some(value)) if is_enum => attrs.rename_all = Some(value),
+            ("tag", Some(value)) => attrs.tag = Some(value),
+            ("tag_value", Some(value)) if !is_enum => attrs.tag_value = Some(value),
+            ("numeric", None) if is_enum => attrs.numeric = true,
+            ("uniform_variants", None) if is_enum => attrs.uniform_variants = true,
+            ("tag_ignore_case", None) if is_enum => attrs.tag_ignore_case = true,
+            ("skip_none", None) => attrs.skip_none = true,
+            ("bound", Some(value)) => {
+                attrs.bound_ser = Some(value.clone());
+                attrs.bound_de = Some(value);
+            }
+            ("bound.serialize", Some(value)) => attrs.bound_ser = Some(value),
+            ("bound.deserialize", Some(value)) => attrs.bound_de = Some(value),
+            (key, _) => return Err(format!("unknown or malformed fastjson container attribute `{}`", key)),
+        }
+    }
+
+    if attrs.external_tag && attrs.tag.is_some() {
+        return Err("`tag` cannot be combined with `external_tag`".to_string());
+    }
+    if attrs.uniform_variants && (attrs.external_tag || attrs.numeric) {
+        return Err("`uniform_variants` cannot be combined with `external_tag` or `numeric`".to_string());
+    }
+    if let Data::Struct(fields) = &data {
+        match (&attrs.tag, &attrs.tag_value, fields) {
+            (None, None, _) => {}
+            (Some(tag), Some(_), Fields::Named(fields)) => {
+                if fields.iter().any(|f| f.ser_name() == *tag || f.de_name() == *tag) {
+                    return Err(format!("field name `{}` conflicts with the struct's `tag`", tag));
                 }
-            }"#).unwrap()
+            }
+            (Some(_), Some(_), _) => return Err("`tag` is only supported on structs with named fields".to_string()),
+            _ => return Err("`tag` and `tag_value` must be used together on a struct".to_string()),
+        }
+    }
+
+    if attrs.skip_none {
+        let field_lists: Vec<&mut Vec<Field>> = match &mut data {
+            Data::Struct(Fields::Named(fields)) => vec![fields],
+            Data::Struct(_) => Vec::new(),
+            Data::Enum(variants) => variants
+                .iter_mut()
+                .filter_map(|v| match &mut v.fields {
+                    Fields::Named(fields) => Some(fields),
+                    _ => None,
+                })
+                .collect(),
+        };
+        for field in field_lists.into_iter().flatten() {
+            if field.is_option && !field.keep_none {
+                field.skip_if_none = true;
+            }
+        }
+    }
+
+    if let (Data::Enum(variants), Some(rule)) = (&mut data, &attrs.rename_all) {
+        for variant in variants.iter_mut().filter(|v| v.rename.is_none()) {
+            variant.rename = Some(rename_variant(rule, &variant.name)?);
+        }
+    }
+
+    if let (Data::Enum(variants), true) = (&data, attrs.tag_ignore_case) {
+        let mut seen: Vec<String> = Vec::new();
+        for variant in variants.iter().filter(|v| v.tag_value.is_none()) {
+            let folded = variant.json_name().to_lowercase();
+            if seen.contains(&folded) {
+                return Err(format!("variant names differ only by case under `tag_ignore_case`: `{}`", variant.json_name()));
+            }
+            seen.push(folded);
+        }
+    }
+
+    if let (Data::Enum(variants), true) = (&data, attrs.numeric) {
+        if attrs.external_tag || attrs.tag.is_some() || attrs.rename_all.is_some() {
+            return Err("`numeric` cannot be combined with `external_tag`, `tag` or `rename_all`".to_string());
+        }
+        for variant in variants {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(format!("`numeric` enums can only have unit variants, found `{}`", variant.name));
+            }
+            if variant.rename.is_some() || variant.tag_value.is_some() {
+                return Err(format!("variant `{}` of a `numeric` enum cannot be renamed or given a `tag_value`", variant.name));
+            }
+        }
+    }
+
+    if let Data::Enum(variants) = &data {
+        let mut seen = Vec::new();
+        for tag in variants.iter().filter_map(|v| v.tag_value) {
+            if attrs.external_tag {
+                return Err("`tag_value` cannot be combined with `external_tag`".to_string());
+            }
+            if seen.contains(&tag) {
+                return Err(format!("duplicate `tag_value` {}", tag));
+            }
+            seen.push(tag);
+        }
+    }
+
+    Ok(Input {
+        name,
+        generic_params: generic_params.join(", "),
+        generic_args,
+        lifetimes,
+        type_params,
+        where_predicates,
+        attrs,
+        data,
+    })
+}
+
+/// Convert a PascalCase variant name to the case convention named by `rename_all`
+///
+/// Rule names and word splitting follow serde: each uppercase letter starts a word.
+fn rename_variant(rule: &str, name: &str) -> Result<String, String> {
+    let mut words: Vec<String> = Vec::new();
+    for c in name.chars() {
+        match words.last_mut() {
+            Some(word) if !c.is_uppercase() => word.push(c),
+            _ => words.push(c.to_string()),
+        }
+    }
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    let upper: Vec<String> = words.iter().map(|w| w.to_uppercase()).collect();
+
+    Ok(match rule {
+        "lowercase" => name.to_lowercase(),
+        "UPPERCASE" => name.to_uppercase(),
+        "PascalCase" => name.to_string(),
+        "camelCase" => {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        "snake_case" => lower.join("_"),
+        "SCREAMING_SNAKE_CASE" => upper.join("_"),
+        "kebab-case" => lower.join("-"),
+        "SCREAMING-KEBAB-CASE" => upper.join("-"),
+        _ => return Err(format!("unknown `rename_all` rule `{}`", rule)),
+    })
+}
+
+/// Consume generic parameters up to the `>` matching an already consumed `<`
+fn take_generic_params(tokens: &mut impl Iterator<Item = TokenTree>) -> Result<Vec<TokenTree>, String> {
+    let mut params = Vec::new();
+    let mut depth = 0usize;
+    for token in tokens.by_ref() {
+        if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+                '<' => depth += 1,
+                '>' if depth == 0 => return Ok(params),
+                '>' => depth -= 1,
+                _ => {}
+            }
+        }
+        params.push(token);
+    }
+    Err("unterminated generic parameters".to_string())
+}
+
+/// The `impl ... for Type<...>` header of a trait impl for the input type
+fn impl_header(input: &Input, trait_path: &str, bound: &Option<String>) -> String {
+    if input.generic_args.is_empty() {
+        return format!("impl {} for {}", trait_path, input.name);
+    }
+
+    // Each type parameter must implement the trait unless `bound` says otherwise
+    let mut predicates: Vec<String> = match bound {
+        Some(bound) => vec![bound.clone()],
+        None => input.type_params.iter().map(|t| format!("{}: {}", t, trait_path)).collect(),
+    };
+    predicates.push(input.where_predicates.clone());
+    predicates.retain(|p| !p.trim().trim_end_matches(',').is_empty());
+    let predicates: Vec<&str> = predicates.iter().map(|p| p.trim().trim_end_matches(',')).collect();
+
+    let header = format!(
+        "impl<{}> {} for {}<{}>",
+        input.generic_params,
+        trait_path,
+        input.name,
+        input.generic_args.join(", ")
+    );
+    if predicates.is_empty() {
+        header
     } else {
-        // Default implementation for any other type
-        TokenStream::from_str(&format!(r#"
-            impl ::fastjson::Serialize for {} {{
-                fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {{
-                    // Default implementation for any other type
-                    use std::collections::HashMap;
-                    let map = HashMap::new();
-                    Ok(::fastjson::Value::Object(map))
-                }}
-            }}"#, type_name)).unwrap()
+        format!("{} where {}", header, predicates.join(", "))
     }
 }
 
-/// Procedural macro for deriving the Deserialize trait.
-#[proc_macro_derive(Deserialize, attributes(fastjson))]
-pub fn derive_deserialize(input: TokenStream) -> TokenStream {
-    // Get the input as a string
-    let input_str = input.to_string();
-    let type_name = extract_type_name(&input_str);
-    
-    // Hard-code the implementation for a few concrete types
-    if type_name == "Person" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for Person {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::Object(map) => {
-                            let name = "John Doe".to_string();
-                            let age = 30;
-                            let is_active = true;
-                            let email = Some("john@example.com".to_string());
-                            let _internal_id = None;
-                            
-                            Ok(Self { name, age, is_active, email, _internal_id })
-                        },
-                        _ => Err(Error::TypeError("expected object for Person".to_string()))
-                    }
-                }
-            }"#).unwrap()
-    } else if type_name == "Status" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for Status {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::String(s) => match s.as_str() {
-                            "Active" => Ok(Status::Active),
-                            "Inactive" => Ok(Status::Inactive),
-                            _ => Ok(Status::Active),
-                        },
-                        Value::Object(map) => {
-                            if let Some(Value::String(t)) = map.get("type") {
-                                match t.as_str() {
-                                    "Pending" => {
-                                        if let Some(Value::Array(arr)) = map.get("data") {
-                                            if arr.len() != 1 {
-                                                return Err(Error::TypeError(format!("expected array with 1 element, found array with {} elements", arr.len())));
-                                            }
-                                            
-                                            let value: String = ::fastjson::Deserialize::deserialize(arr[0].clone())?;
-                                            return Ok(Status::Pending(value));
-                                        }
-                                        Err(Error::TypeError("expected array for enum variant data".to_string()))
-                                    },
-                                    "Custom" => {
-                                        let code = match map.get("code") {
-                                            Some(v) => ::fastjson::Deserialize::deserialize(v.clone())?,
-                                            None => return Err(Error::MissingField("code".to_string())),
-                                        };
-                                        
-                                        let message = match map.get("message") {
-                                            Some(v) => ::fastjson::Deserialize::deserialize(v.clone())?,
-                                            None => return Err(Error::MissingField("message".to_string())),
-                                        };
-                                        
-                                        return Ok(Status::Custom { code, message });
-                                    },
-                                    _ => Err(Error::TypeError(format!("unknown enum variant type: {}", t))),
-                                }
-                            } else {
-                                Err(Error::MissingField("type".to_string()))
-                            }
-                        },
-                        _ => Err(Error::TypeError("expected string or object for enum".to_string()))
-                    }
-                }
-            }"#).unwrap()
-    } else if type_name == "SimpleEnum_One" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for SimpleEnum {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::String(s) => match s.as_str() {
-                            "One" => Ok(SimpleEnum::One),
-                            _ => Ok(SimpleEnum::One),
-                        },
-                        Value::Object(map) => {
-                            if let Some(Value::String(t)) = map.get("type") {
-                                match t.as_str() {
-                                    "Two" => {
-                                        if let Some(Value::Array(arr)) = map.get("data") {
-                                            if arr.len() != 1 {
-                                                return Err(Error::TypeError(format!(
-                                                    "expected array with 1 element, found array with {} elements", 
-                                                    arr.len()
-                                                )));
-                                            }
-                                            
-                                            let s = ::fastjson::Deserialize::deserialize(arr[0].clone())?;
-                                            return Ok(SimpleEnum::Two(s));
-                                        }
-                                        Err(Error::TypeError("expected array for enum variant data".to_string()))
-                                    },
-                                    "Three" => {
-                                        if let Some(val) = map.get("value") {
-                                            let value = ::fastjson::Deserialize::deserialize(val.clone())?;
-                                            return Ok(SimpleEnum::Three { value });
-                                        }
-                                        Err(Error::MissingField("value".to_string()))
-                                    },
-                                    _ => Err(Error::TypeError(format!("unknown enum variant type: {}", t))),
-                                }
-                            } else {
-                                Err(Error::MissingField("type".to_string()))
-                            }
-                        },
-                        _ => Err(Error::TypeError("expected string or object for enum".to_string()))
-                    }
-                }
-            }"#).unwrap()
-    } else if type_name == "SimpleEnum_First" || type_name == "SimpleEnum" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for SimpleEnum {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::String(s) => match s.as_str() {
-                            "First" => Ok(SimpleEnum::First),
-                            "Second" => Ok(SimpleEnum::Second),
-                            _ => Ok(SimpleEnum::First),
-                        },
-                        _ => Err(Error::TypeError("expected string for enum".to_string()))
-                    }
-                }
-            }"#).unwrap()
-    } else if type_name == "SimpleTest" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for SimpleTest {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::Object(map) => {
-                            Ok(Self { 
-                                name: "test".to_string(), 
-                                value: 42 
-                            })
-                        },
-                        _ => Err(Error::TypeError("expected object".to_string()))
-                    }
+/// Split a token stream on every comma outside of groups
+fn split_on_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    for token in stream {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == ',' => parts.push(Vec::new()),
+            _ => parts.last_mut().unwrap().push(token),
+        }
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Split a token stream on commas that are not nested inside `<...>`
+fn split_top_level(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0usize;
+    let mut prev_joint_dash = false;
+
+    for token in stream {
+        let mut joint_dash = false;
+        if let TokenTree::Punct(p) = &token {
+            match p.as_char() {
+                ',' if depth == 0 => {
+                    parts.push(std::mem::take(&mut current));
+                    continue;
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleString" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for SimpleString {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::Object(_) => {
-                            Ok(Self { text: "Hello world".to_string() })
-                        },
-                        _ => Err(Error::TypeError("expected object".to_string()))
-                    }
+                '<' => depth += 1,
+                // `->` in fn pointer types is not a closing angle bracket
+                '>' if !prev_joint_dash => depth = depth.saturating_sub(1),
+                '-' => joint_dash = p.spacing() == Spacing::Joint,
+                _ => {}
+            }
+        }
+        prev_joint_dash = joint_dash;
+        current.push(token);
+    }
+
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Pull `key` / `key = value` items out of a `#[fastjson(...)]` attribute body.
+///
+/// The stream is the contents of the attribute's brackets. Non-fastjson
+/// attributes (docs, cfgs, other derives' helpers) yield no items.
+fn extract_attrs(stream: TokenStream) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut tokens = stream.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ident)) if ident.to_string() == "fastjson" => {}
+        _ => return Ok(Vec::new()),
+    }
+    let list = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => group,
+        _ => return Err("expected #[fastjson(...)]".to_string()),
+    };
+    extract_meta_list(list.stream())
+}
+
+/// Parse comma-separated `key`, `key = literal` and `key(...)` items
+fn extract_meta_list(stream: TokenStream) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut metas = Vec::new();
+    for item in split_top_level(stream) {
+        let mut item = item.into_iter();
+        let key = match item.next() {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            Some(other) => return Err(format!("unexpected `{}` in fastjson attribute", other)),
+            None => continue,
+        };
+        let value = match item.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '=' => match item.next() {
+                Some(TokenTree::Literal(lit)) => Some(parse_literal(&lit.to_string())),
+                _ => return Err(format!("expected a literal value for `{}`", key)),
+            },
+            // A nested list such as `rename(serialize = "a")` becomes `rename.serialize`
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                for (nested_key, value) in extract_meta_list(group.stream())? {
+                    metas.push((format!("{}.{}", key, nested_key), value));
                 }
-            }"#).unwrap()
-    } else if type_name == "SimpleColors" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for SimpleColors {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::String(s) => match s.as_str() {
-                            "Red" => Ok(SimpleColors::Red),
-                            _ => Ok(SimpleColors::Red),
-                        },
-                        _ => Err(Error::TypeError("expected string for enum".to_string()))
-                    }
+                continue;
+            }
+            None => None,
+            Some(other) => return Err(format!("unexpected `{}` after `{}`", other, key)),
+        };
+        metas.push((key, value));
+    }
+    Ok(metas)
+}
+
+/// Consume leading `#[...]` attributes from a token list, returning fastjson items
+fn take_attrs(tokens: &mut std::iter::Peekable<std::vec::IntoIter<TokenTree>>) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut metas = Vec::new();
+    while let Some(TokenTree::Punct(p)) = tokens.peek() {
+        if p.as_char() != '#' {
+            break;
+        }
+        tokens.next();
+        match tokens.next() {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                metas.extend(extract_attrs(group.stream())?);
+            }
+            _ => return Err("expected attribute after `#`".to_string()),
+        }
+    }
+    Ok(metas)
+}
+
+/// Turn a literal token into its value, unquoting string literals
+fn parse_literal(lit: &str) -> String {
+    if let Some(raw) = lit.strip_prefix('r') {
+        let raw = raw.trim_matches('#');
+        return raw[1..raw.len() - 1].to_string();
+    }
+    if lit.len() >= 2 && lit.starts_with('"') && lit.ends_with('"') {
+        let mut result = String::new();
+        let mut chars = lit[1..lit.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('r') => result.push('\r'),
+                    Some('0') => result.push('\0'),
+                    Some(other) => result.push(other),
+                    None => {}
                 }
-            }"#).unwrap()
-    } else if type_name == "TestOptional" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for TestOptional {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::Object(_) => {
-                            Ok(Self {
-                                required: "hello".to_string(),
-                                optional: Some("world".to_string()),
-                                conditional: Some(42),
-                            })
-                        },
-                        _ => Err(Error::TypeError("expected object".to_string()))
-                    }
+            } else {
+                result.push(c);
+            }
+        }
+        return result;
+    }
+    lit.to_string()
+}
+
+fn extract_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, String> {
+    let fields: Vec<Field> = split_top_level(stream)
+        .into_iter()
+        .enumerate()
+        .map(|(index, tokens)| extract_field(tokens, index, named))
+        .collect::<Result<_, _>>()?;
+
+    if fields.iter().filter(|f| f.flatten).count() > 1 {
+        return Err("only one field can be marked `flatten`".to_string());
+    }
+    let mut orders: Vec<u32> = fields.iter().filter_map(|f| f.order).collect();
+    orders.sort_unstable();
+    if let Some(pair) = orders.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!("duplicate `order` {}", pair[0]));
+    }
+    // Missing array elements can only be filled from the end
+    if !named {
+        let mut read = fields.iter().filter(|f| !f.skip_de);
+        if read.by_ref().any(|f| f.default) && read.any(|f| !f.default) {
+            return Err("`default` tuple fields must come after all other deserialized fields".to_string());
+        }
+    }
+    Ok(fields)
+}
+
+/// Parse a single field declaration: attributes, visibility, name and type
+fn extract_field(tokens: Vec<TokenTree>, index: usize, named: bool) -> Result<Field, String> {
+    let mut tokens = tokens.into_iter().peekable();
+    let metas = take_attrs(&mut tokens)?;
+
+    // Visibility: `pub`, `pub(crate)`, `pub(self)`, `pub(super)`, `pub(in path)`.
+    // A parenthesized group after `pub` may also be a tuple type, as in `pub (u8, u8)`.
+    if let Some(TokenTree::Ident(ident)) = tokens.peek() {
+        if ident.to_string() == "pub" {
+            tokens.next();
+            if let Some(TokenTree::Group(group)) = tokens.peek() {
+                if group.delimiter() == Delimiter::Parenthesis && is_visibility_scope(group.stream()) {
+                    tokens.next();
                 }
-            }"#).unwrap()
-    } else if type_name == "Container" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for Container {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    
-                    let items = vec![
-                        Item { id: 1, name: "Item 1".to_string() },
-                        Item { id: 2, name: "Item 2".to_string() }
-                    ];
-                    
-                    Ok(Self { 
-                        title: "Test Container".to_string(),
-                        items
-                    })
+            }
+        }
+    }
+
+    let member = if named {
+        let name = match tokens.next() {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            _ => return Err("expected a field name".to_string()),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == ':' => {}
+            _ => return Err(format!("expected `:` after field `{}`", name)),
+        }
+        name
+    } else {
+        index.to_string()
+    };
+
+    let ty_tokens: Vec<TokenTree> = tokens.collect();
+    let option_inner = option_inner_type(&ty_tokens);
+    let is_option = option_inner.is_some();
+    let is_double_option = option_inner.is_some_and(|inner| option_inner_type(inner).is_some());
+    let ty = TokenStream::from_iter(ty_tokens).to_string();
+
+    let mut field = Field {
+        member,
+        ty,
+        is_option,
+        is_double_option,
+        rename_ser: None,
+        rename_de: None,
+        aliases: Vec::new(),
+        skip_ser: false,
+        skip_de: false,
+        default: false,
+        skip_if_none: false,
+        keep_none: false,
+        as_string: false,
+        one_or_many: false,
+        flatten: false,
+        with: None,
+        validate: None,
+        order: None,
+    };
+
+    for (key, value) in metas {
+        match (key.as_str(), value) {
+            ("rename", Some(value)) => {
+                field.rename_ser = Some(value.clone());
+                field.rename_de = Some(value);
+            }
+            ("rename.serialize", Some(value)) => field.rename_ser = Some(value),
+            ("rename.deserialize", Some(value)) => field.rename_de = Some(value),
+            ("alias", Some(value)) => field.aliases.push(value),
+            ("skip", None) => {
+                field.skip_ser = true;
+                field.skip_de = true;
+            }
+            ("skip_serializing", None) => field.skip_ser = true,
+            ("skip_deserializing", None) => field.skip_de = true,
+            ("default", None) => field.default = true,
+            ("skip_if_none", None) => field.skip_if_none = true,
+            ("keep_none", None) => field.keep_none = true,
+            ("as_string", None) => field.as_string = true,
+            ("one_or_many", None) => field.one_or_many = true,
+            ("with", Some(value)) => field.with = Some(value),
+            ("validate", Some(value)) => field.validate = Some(value),
+            ("bytes_as_base64", None) => field.with = Some("::fastjson::base64".to_string()),
+            ("columnar", None) => field.with = Some("::fastjson::columnar".to_string()),
+            ("order", Some(value)) if named => match value.parse() {
+                Ok(value) => field.order = Some(value),
+                Err(_) => return Err(format!("expected a non-negative integer for `order`, found `{}`", value)),
+            },
+            ("order", Some(_)) => return Err("`order` is only supported on named fields".to_string()),
+            ("flatten", None) if named => field.flatten = true,
+            ("flatten", None) => return Err("`flatten` is only supported on named fields".to_string()),
+            (key, _) => return Err(format!("unknown or malformed fastjson field attribute `{}`", key)),
+        }
+    }
+
+    if field.keep_none && field.skip_if_none {
+        return Err("`keep_none` cannot be combined with `skip_if_none`".to_string());
+    }
+    Ok(field)
+}
+
+/// Whether the contents of `pub(...)` name a visibility scope rather than a tuple type
+fn is_visibility_scope(stream: TokenStream) -> bool {
+    match stream.into_iter().next() {
+        Some(TokenTree::Ident(ident)) => {
+            matches!(ident.to_string().as_str(), "crate" | "self" | "super" | "in")
+        }
+        _ => false,
+    }
+}
+
+/// The type argument of an `Option<...>` type, possibly written with a path prefix
+fn option_inner_type(tokens: &[TokenTree]) -> Option<&[TokenTree]> {
+    let mut last_ident = None;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(ident) => last_ident = Some(ident.to_string()),
+            TokenTree::Punct(p) if p.as_char() == ':' => {}
+            TokenTree::Punct(p) if p.as_char() == '<' => {
+                if last_ident.as_deref() != Some("Option") || tokens.len() < index + 2 {
+                    return None;
                 }
-            }"#).unwrap()
-    } else if type_name == "RequiredField" {
-        TokenStream::from_str(r#"
-            impl ::fastjson::Deserialize for RequiredField {
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {
-                    use ::fastjson::{Value, Error};
-                    match value {
-                        Value::Object(map) => {
-                            let required_field = match map.get("required_field") {
-                                Some(v) => ::fastjson::Deserialize::deserialize(v.clone())?,
-                                None => return Err(Error::MissingField("required_field".to_string())),
-                            };
-                            
-                            Ok(Self { required_field })
-                        },
-                        _ => Err(Error::TypeError("expected object".to_string()))
+                // Everything between the opening `<` and the final `>`
+                return Some(&tokens[index + 1..tokens.len() - 1]);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn extract_variants(stream: TokenStream) -> Result<Vec<Variant>, String> {
+    // Variant fields sit inside groups, so any `<` at this level belongs to a
+    // discriminant expression like `1 << 2` and must not hide later commas
+    split_on_commas(stream)
+        .into_iter()
+        .map(extract_single_variant)
+        .collect()
+}
+
+/// Parse a single enum variant along with its attributes and fields
+fn extract_single_variant(tokens: Vec<TokenTree>) -> Result<Variant, String> {
+    let mut tokens = tokens.into_iter().peekable();
+    let metas = take_attrs(&mut tokens)?;
+
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a variant name".to_string()),
+    };
+
+    let fields = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            Fields::Named(extract_fields(group.stream(), true)?)
+        }
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+            Fields::Unnamed(extract_fields(group.stream(), false)?)
+        }
+        // Unit variant, possibly with an explicit discriminant
+        _ => Fields::Unit,
+    };
+
+    let mut rename = None;
+    let mut tag_value = None;
+    for (key, value) in metas {
+        match (key.as_str(), value) {
+            ("rename", Some(value)) => rename = Some(value),
+            ("tag_value", Some(value)) => match value.parse() {
+                Ok(value) => tag_value = Some(value),
+                Err(_) => return Err(format!("expected an integer for `tag_value`, found `{}`", value)),
+            },
+            (key, _) => return Err(format!("unknown or malformed fastjson variant attribute `{}`", key)),
+        }
+    }
+
+    Ok(Variant { name, rename, tag_value, fields })
+}
+
+/// Bindings used to refer to fields inside generated code (`__field0`, ...)
+fn binding(index: usize) -> String {
+    format!("__field{}", index)
+}
+
+/// Expression serializing a field, given an expression that borrows it
+fn serialize_field_expr(field: &Field, access: &str) -> String {
+    if let Some(with) = &field.with {
+        format!("{}::serialize({})?", with, access)
+    } else if field.as_string {
+        format!(
+            "::fastjson::Value::String(::std::string::ToString::to_string({}))",
+            access
+        )
+    } else {
+        format!("::fastjson::Serialize::serialize({})?", access)
+    }
+}
+
+/// Fields paired with their accesses, in the order they are serialized
+///
+/// Fields with an `order` go first by number, the rest in declaration order.
+fn serialization_order<'a>(fields: &'a [Field], accesses: &'a [String]) -> Vec<(&'a Field, &'a String)> {
+    let mut ordered: Vec<(&Field, &String)> = fields.iter().zip(accesses).collect();
+    ordered.sort_by_key(|(field, _)| field.order.map_or((1, 0), |order| (0, order)));
+    ordered
+}
+
+/// Statement driving `serializer` through a field, given an expression that borrows it
+fn serialize_field_to(field: &Field, access: &str) -> String {
+    if let Some(with) = &field.with {
+        format!("::fastjson::Serialize::serialize_to(&{}::serialize({})?, serializer)?;\n", with, access)
+    } else if field.as_string {
+        format!("serializer.serialize_str(&::std::string::ToString::to_string({}))?;\n", access)
+    } else {
+        format!("::fastjson::Serialize::serialize_to({}, serializer)?;\n", access)
+    }
+}
+
+/// Statements writing named fields to `serializer` as one map
+///
+/// `tag` is a `(key, value)` entry written first. Flattened fields are not
+/// supported here, since their entries are only known once serialized.
+fn serialize_named_fields_to(fields: &[Field], accesses: &[String], tag: Option<(&str, &str)>) -> String {
+    let ordered: Vec<(&Field, &String)> =
+        serialization_order(fields, accesses).into_iter().filter(|(field, _)| !field.skip_ser).collect();
+
+    // Keys skipped when `None` only count towards the length when present
+    let optional = |field: &Field| field.skip_if_none || (field.is_double_option && field.with.is_none());
+    let mut len = vec![(ordered.iter().filter(|(field, _)| !optional(field)).count() + tag.iter().count()).to_string()];
+    for (field, access) in &ordered {
+        if optional(field) {
+            len.push(format!("usize::from(::std::option::Option::is_some({}))", access));
+        }
+    }
+
+    let mut code = format!("serializer.serialize_map({})?;\n", len.join(" + "));
+    if let Some((key, value)) = tag {
+        code.push_str(&format!("serializer.serialize_key({:?})?;\nserializer.serialize_str({:?})?;\n", key, value));
+    }
+    for (field, access) in ordered {
+        let key = format!("serializer.serialize_key({:?})?;\n", field.ser_name());
+        if optional(field) {
+            code.push_str(&format!(
+                "if let ::std::option::Option::Some(__some) = {} {{\n{}{}}}\n",
+                access,
+                key,
+                serialize_field_to(field, "__some")
+            ));
+        } else {
+            code.push_str(&key);
+            code.push_str(&serialize_field_to(field, access));
+        }
+    }
+    code.push_str("serializer.end_map()");
+    code
+}
+
+/// Statements inserting named fields into a `map` being built for serialization
+fn serialize_named_fields(fields: &[Field], accesses: &[String]) -> String {
+    let mut code = String::new();
+    let mut flattened = None;
+    for (field, access) in serialization_order(fields, accesses) {
+        if field.skip_ser {
+            continue;
+        }
+        if field.flatten {
+            flattened = Some((field, access));
+            continue;
+        }
+        let key = format!("{:?}.to_string()", field.ser_name());
+        // A `None` double option is written as an absent key, `Some(None)` as null
+        if field.skip_if_none || (field.is_double_option && field.with.is_none()) {
+            code.push_str(&format!(
+                "if let ::std::option::Option::Some(__some) = {} {{ map.insert({}, {}); }}\n",
+                access,
+                key,
+                serialize_field_expr(field, "__some")
+            ));
+        } else {
+            code.push_str(&format!(
+                "map.insert({}, {});\n",
+                key,
+                serialize_field_expr(field, access)
+            ));
+        }
+    }
+
+    // Flattened entries go in last and never replace a declared field
+    if let Some((field, access)) = flattened {
+        code.push_str(&format!(
+            r#"match {} {{
+                ::fastjson::Value::Object(__extra) => {{
+                    for (__key, __value) in __extra {{
+                        map.entry(__key).or_insert(__value);
+                    }}
+                }}
+                __other => return ::std::result::Result::Err(::fastjson::Error::custom(::std::format!(
+                    "flattened field `{}` must serialize to an object, found {{}}", __other.type_name()
+                ))),
+            }}
+            "#,
+            serialize_field_expr(field, access),
+            field.member
+        ));
+    }
+    code
+}
+
+/// Expression building a `Value::Array` from tuple fields
+fn serialize_unnamed_fields(fields: &[Field], accesses: &[String]) -> String {
+    let items: Vec<String> = fields
+        .iter()
+        .zip(accesses)
+        .filter(|(field, _)| !field.skip_ser)
+        .map(|(field, access)| serialize_field_expr(field, access))
+        .collect();
+    format!("::fastjson::Value::Array(::std::vec![{}])", items.join(", "))
+}
+
+/// Expression building a `Value::Object` by running `inserts` against `map`
+fn object_expr(inserts: &str) -> String {
+    if inserts.is_empty() {
+        "::fastjson::Value::Object(::fastjson::Map::new())".to_string()
+    } else {
+        format!(
+            "{{ let mut map = ::fastjson::Map::new();\n{}::fastjson::Value::Object(map) }}",
+            inserts
+        )
+    }
+}
+
+fn object_body(inserts: &str) -> String {
+    format!("::std::result::Result::Ok({})", object_expr(inserts))
+}
+
+fn generate_struct_serialize(input: &Input, fields: &Fields) -> String {
+    let accesses: Vec<String> = match fields {
+        Fields::Named(fields) | Fields::Unnamed(fields) => fields.iter().map(|f| format!("&self.{}", f.member)).collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let body = match fields {
+        Fields::Named(fields) => {
+            let mut inserts = String::new();
+            // The discriminator comes first so consumers can dispatch on it early
+            if let (Some(tag), Some(tag_value)) = (&input.attrs.tag, &input.attrs.tag_value) {
+                inserts.push_str(&format!(
+                    "map.insert({:?}.to_string(), ::fastjson::Value::from({:?}));\n",
+                    tag, tag_value
+                ));
+            }
+            inserts.push_str(&serialize_named_fields(fields, &accesses));
+            object_body(&inserts)
+        }
+        Fields::Unnamed(fields) => {
+            format!("::std::result::Result::Ok({})", serialize_unnamed_fields(fields, &accesses))
+        }
+        Fields::Unit => "::std::result::Result::Ok(::fastjson::Value::Null)".to_string(),
+    };
+
+    // Stream fields straight to the serializer, so a large field such as a
+    // `Vec<u8>` is not built into a `Value` first. Flattened fields keep the
+    // default, which goes through `serialize`.
+    let body_to = match fields {
+        Fields::Named(fields) if fields.iter().any(|f| f.flatten && !f.skip_ser) => None,
+        Fields::Named(fields) => {
+            let tag = match (&input.attrs.tag, &input.attrs.tag_value) {
+                (Some(tag), Some(tag_value)) => Some((tag.as_str(), tag_value.as_str())),
+                _ => None,
+            };
+            Some(serialize_named_fields_to(fields, &accesses, tag))
+        }
+        Fields::Unnamed(fields) => {
+            let mut code = format!(
+                "serializer.serialize_seq({})?;\n",
+                fields.iter().filter(|f| !f.skip_ser).count()
+            );
+            for (field, access) in fields.iter().zip(&accesses).filter(|(f, _)| !f.skip_ser) {
+                code.push_str(&serialize_field_to(field, access));
+            }
+            code.push_str("serializer.end_seq()");
+            Some(code)
+        }
+        Fields::Unit => Some("serializer.serialize_null()".to_string()),
+    };
+    let serialize_to = body_to.map_or(String::new(), |body_to| {
+        format!(
+            r#"
+            fn serialize_to(&self, serializer: &mut dyn ::fastjson::Serializer) -> ::fastjson::Result<()> {{
+                {}
+            }}"#,
+            body_to
+        )
+    });
+
+    format!(
+        r#"
+        {} {{
+            fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {{
+                {}
+            }}
+            {}
+        }}"#,
+        impl_header(input, "::fastjson::Serialize", &input.attrs.bound_ser),
+        body,
+        serialize_to
+    )
+}
+
+fn generate_enum_serialize(input: &Input, variants: &[Variant]) -> String {
+    let name = &input.name;
+    if input.attrs.numeric {
+        return generate_numeric_enum_serialize(input, variants);
+    }
+    let tag_key = input.attrs.tag_key();
+    let mut arms = String::new();
+    for variant in variants {
+        let json_name = variant.json_name();
+        let bindings: Vec<String> = match &variant.fields {
+            Fields::Named(fields) | Fields::Unnamed(fields) => (0..fields.len()).map(binding).collect(),
+            Fields::Unit => Vec::new(),
+        };
+
+        let pattern = match &variant.fields {
+            Fields::Unit => format!("{}::{}", name, variant.name),
+            Fields::Unnamed(_) => format!("{}::{}({})", name, variant.name, bindings.join(", ")),
+            Fields::Named(fields) => {
+                let patterns: Vec<String> = fields
+                    .iter()
+                    .zip(&bindings)
+                    .map(|(field, binding)| format!("{}: {}", field.member, binding))
+                    .collect();
+                format!("{}::{} {{ {} }}", name, variant.name, patterns.join(", "))
+            }
+        };
+
+        // Integer discriminants are written to the tag instead of the name
+        let tag_expr = match variant.tag_value {
+            Some(tag) => format!("::fastjson::Value::Number(::fastjson::Number::from({}i64))", tag),
+            None => format!("::fastjson::Value::String({:?}.to_string())", json_name),
+        };
+
+        let body = match &variant.fields {
+            Fields::Unit if variant.tag_value.is_some() || input.attrs.uniform_variants => {
+                object_body(&format!("map.insert({:?}.to_string(), {});\n", tag_key, tag_expr))
+            }
+            Fields::Unit => format!(
+                "::std::result::Result::Ok(::fastjson::Value::String({:?}.to_string()))",
+                json_name
+            ),
+            // {"Variant": content}, where a single tuple field is not wrapped in an array
+            _ if input.attrs.external_tag => {
+                let content = match &variant.fields {
+                    Fields::Unnamed(fields) if fields.len() == 1 => {
+                        serialize_field_expr(&fields[0], &bindings[0])
                     }
-                }
-            }"#).unwrap()
+                    Fields::Unnamed(fields) => serialize_unnamed_fields(fields, &bindings),
+                    Fields::Named(fields) => object_expr(&serialize_named_fields(fields, &bindings)),
+                    Fields::Unit => unreachable!(),
+                };
+                object_body(&format!("map.insert({:?}.to_string(), {});\n", json_name, content))
+            }
+            // {"type": "Variant", "data": [...]} or {"type": "Variant", field: ...}
+            fields => {
+                let tag = format!("map.insert({:?}.to_string(), {});\n", tag_key, tag_expr);
+                let inserts = match fields {
+                    Fields::Unnamed(fields) => format!(
+                        "map.insert(\"data\".to_string(), {});\n",
+                        serialize_unnamed_fields(fields, &bindings)
+                    ),
+                    Fields::Named(fields) => serialize_named_fields(fields, &bindings),
+                    Fields::Unit => unreachable!(),
+                };
+                object_body(&format!("{}{}", tag, inserts))
+            }
+        };
+
+        arms.push_str(&format!("{} => {{\n{}\n}}\n", pattern, body));
+    }
+
+    // An empty enum has no values, so matching on the dereferenced value is exhaustive
+    let scrutinee = if variants.is_empty() { "*self" } else { "self" };
+
+    format!(
+        r#"
+        {} {{
+            #[allow(unused_variables)]
+            fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {{
+                match {} {{
+                    {}
+                }}
+            }}
+        }}"#,
+        impl_header(input, "::fastjson::Serialize", &input.attrs.bound_ser),
+        scrutinee,
+        arms
+    )
+}
+
+/// Serialize a `numeric` enum as its discriminant, e.g. `1` for `Level::Medium`
+fn generate_numeric_enum_serialize(input: &Input, variants: &[Variant]) -> String {
+    let name = &input.name;
+    let arms: String = variants
+        .iter()
+        .map(|variant| {
+            format!(
+                "{name}::{v} => ::std::result::Result::Ok(::fastjson::Value::Number(::fastjson::Number::from({name}::{v} as i64))),\n",
+                name = name,
+                v = variant.name
+            )
+        })
+        .collect();
+    let scrutinee = if variants.is_empty() { "*self" } else { "self" };
+
+    format!(
+        r#"
+        {} {{
+            fn serialize(&self) -> ::fastjson::Result<::fastjson::Value> {{
+                match {} {{
+                    {}
+                }}
+            }}
+        }}"#,
+        impl_header(input, "::fastjson::Serialize", &input.attrs.bound_ser),
+        scrutinee,
+        arms
+    )
+}
+
+/// `Result` expression deserializing a field from an owned `Value` expression
+fn deserialize_field_expr(field: &Field, value: &str) -> String {
+    if let Some(with) = &field.with {
+        format!("{}::deserialize({})", with, value)
+    } else if field.as_string {
+        format!(
+            r#"match {} {{
+                ::fastjson::Value::String(s) => s.parse().map_err(|_| ::fastjson::Error::TypeError(
+                    ::std::format!("invalid {} string: {{}}", s)
+                )),
+                other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                    ::std::format!("expected string, found {{}}", other.type_name())
+                )),
+            }}"#,
+            value,
+            field.ty.replace('{', "{{").replace('}', "}}").replace('"', "\\\"")
+        )
+    } else if field.one_or_many {
+        format!(
+            r#"match {} {{
+                __array @ ::fastjson::Value::Array(_) => ::fastjson::Deserialize::deserialize(__array),
+                __single => ::fastjson::Deserialize::deserialize(__single).map(|item| ::std::vec![item]),
+            }}"#,
+            value
+        )
+    } else {
+        format!("::fastjson::Deserialize::deserialize({})", value)
+    }
+}
+
+/// Statements binding `__fieldN` for each named field, moved out of `map`
+///
+/// A flattened field is bound last, from whatever keys remain in `map`.
+fn deserialize_named_fields(fields: &[Field]) -> String {
+    let mut code = String::new();
+    let mut flattened = None;
+    for (index, field) in fields.iter().enumerate() {
+        if field.flatten && !field.skip_de {
+            flattened = Some((index, field));
+            continue;
+        }
+        if field.skip_de {
+            code.push_str(&format!(
+                "let {} = ::std::default::Default::default();\n",
+                binding(index)
+            ));
+            continue;
+        }
+
+        let key = format!("{:?}", field.de_name());
+        let missing = if field.default {
+            "::std::default::Default::default()".to_string()
+        } else if field.is_option {
+            "::std::option::Option::None".to_string()
+        } else {
+            format!(
+                "return ::std::result::Result::Err(::fastjson::Error::MissingField({}.to_string()))",
+                key
+            )
+        };
+        // Every alias is removed, so none is left over for a flattened field;
+        // the first key found wins
+        let mut lookup = format!("map.remove({})", key);
+        for alias in &field.aliases {
+            lookup.push_str(&format!(".or(map.remove({:?}))", alias));
+        }
+        let mut expr = deserialize_field_expr(field, "v");
+        if field.is_double_option && field.with.is_none() {
+            // A present key is `Some`, even when its value is null
+            expr.push_str(".map(::std::option::Option::Some)");
+        }
+
+        // Type errors name the field they came from
+        code.push_str(&format!(
+            r#"let {} = match {} {{
+                ::std::option::Option::Some(v) => {}.map_err(|e| match e {{
+                    ::fastjson::Error::TypeError(msg) => ::fastjson::Error::TypeError(
+                        ::std::format!("field `{}`: {{}}", msg)
+                    ),
+                    other => other,
+                }})?,
+                ::std::option::Option::None => {},
+            }};
+            "#,
+            binding(index),
+            lookup,
+            expr,
+            field.de_name().replace('{', "{{").replace('}', "}}").replace('"', "\\\""),
+            missing
+        ));
+    }
+
+    if let Some((index, field)) = flattened {
+        code.push_str(&format!(
+            "let {} = {}?;\n",
+            binding(index),
+            deserialize_field_expr(field, "::fastjson::Value::Object(map)")
+        ));
+    }
+    code.push_str(&validate_fields(fields));
+    code
+}
+
+/// Statements running each field's `validate` function on its `__fieldN` binding
+fn validate_fields(fields: &[Field]) -> String {
+    let mut code = String::new();
+    for (index, field) in fields.iter().enumerate() {
+        let Some(validate) = field.validate.as_ref().filter(|_| !field.skip_de) else {
+            continue;
+        };
+        code.push_str(&format!(
+            r#"if let ::std::result::Result::Err(msg) = {}(&{}) {{
+                return ::std::result::Result::Err(::fastjson::Error::custom(
+                    ::std::format!("field `{}`: {{}}", msg)
+                ));
+            }}
+            "#,
+            validate,
+            binding(index),
+            field.de_name().replace('{', "{{").replace('}', "}}").replace('"', "\\\"")
+        ));
+    }
+    code
+}
+
+/// Statements binding `__fieldN` for each tuple field, moved out of the array `arr`
+///
+/// Trailing `default` fields may be missing from a shorter array.
+fn deserialize_unnamed_fields(name: &str, fields: &[Field]) -> String {
+    let expected = fields.iter().filter(|f| !f.skip_de).count();
+    let required = fields.iter().filter(|f| !f.skip_de && !f.default).count();
+    let (check, arity) = if required == expected {
+        (format!("arr.len() != {}", expected), expected.to_string())
+    } else if required == 0 {
+        (format!("arr.len() > {}", expected), format!("at most {}", expected))
     } else {
-        // Default implementation for any other type
-        TokenStream::from_str(&format!(r#"
-            impl ::fastjson::Deserialize for {} {{
-                fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {{
-                    // Dummy implementation to make it compile
-                    use ::fastjson::Error;
-                    
-                    Err(Error::TypeError("Not yet implemented".to_string()))
+        (
+            format!("arr.len() < {} || arr.len() > {}", required, expected),
+            format!("{} to {}", required, expected),
+        )
+    };
+    let mut code = format!(
+        r#"if {} {{
+            return ::std::result::Result::Err(::fastjson::Error::TypeError(::std::format!(
+                "expected array with {} elements for {}, found array with {{}} elements", arr.len()
+            )));
+        }}
+        let mut __items = arr.into_iter();
+        "#,
+        check, arity, name
+    );
+
+    for (index, field) in fields.iter().enumerate() {
+        if field.skip_de {
+            code.push_str(&format!(
+                "let {} = ::std::default::Default::default();\n",
+                binding(index)
+            ));
+        } else if field.default {
+            code.push_str(&format!(
+                r#"let {} = match __items.next() {{
+                    ::std::option::Option::Some(v) => {}?,
+                    ::std::option::Option::None => ::std::default::Default::default(),
+                }};
+                "#,
+                binding(index),
+                deserialize_field_expr(field, "v")
+            ));
+        } else {
+            code.push_str(&format!(
+                "let {} = {}?;\n",
+                binding(index),
+                // The length was checked above, so there is an element for every field
+                deserialize_field_expr(field, "__items.next().unwrap()")
+            ));
+        }
+    }
+    code.push_str(&validate_fields(fields));
+    code
+}
+
+/// Expression constructing `path` from the `__fieldN` bindings
+fn construct(path: &str, fields: &Fields) -> String {
+    match fields {
+        Fields::Named(fields) => {
+            let inits: Vec<String> = fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| format!("{}: {}", field.member, binding(index)))
+                .collect();
+            format!("{} {{ {} }}", path, inits.join(", "))
+        }
+        Fields::Unnamed(fields) => {
+            let inits: Vec<String> = (0..fields.len()).map(binding).collect();
+            format!("{}({})", path, inits.join(", "))
+        }
+        Fields::Unit => path.to_string(),
+    }
+}
+
+/// Statements removing a struct's discriminator from `map`, failing unless it
+/// holds the expected value
+fn check_struct_tag(name: &str, tag: &str, tag_value: &str) -> String {
+    format!(
+        r#"match map.remove({tag:?}) {{
+            ::std::option::Option::Some(::fastjson::Value::String(__tag)) if __tag == {tag_value:?} => {{}}
+            ::std::option::Option::Some(__other) => {{
+                let __found = match &__other {{
+                    ::fastjson::Value::String(__tag) => ::std::format!("{{:?}}", __tag),
+                    __other => __other.type_name().to_string(),
+                }};
+                return ::std::result::Result::Err(::fastjson::Error::TypeError(::std::format!(
+                    "expected {{:?}} to be {{:?}} for {name}, found {{}}", {tag:?}, {tag_value:?}, __found
+                )));
+            }}
+            ::std::option::Option::None => {{
+                return ::std::result::Result::Err(::fastjson::Error::MissingField({tag:?}.to_string()));
+            }}
+        }}
+        "#
+    )
+}
+
+fn generate_struct_deserialize(input: &Input, fields: &Fields) -> String {
+    let name = &input.name;
+    let body = match fields {
+        Fields::Named(named) => format!(
+            r#"match value {{
+                ::fastjson::Value::Object(mut map) => {{
+                    {}{}
+                    ::std::result::Result::Ok({})
                 }}
-            }}"#, type_name)).unwrap()
-    }
-}
-
-/// Extract the type name from a token stream string
-fn extract_type_name(input: &str) -> String {
-    // Simple extraction of the type name from the token stream
-    // This is very naive but should work for our test cases
-    
-    if input.contains("struct Person") {
-        return "Person".to_string();
-    } else if input.contains("enum Status") {
-        return "Status".to_string();
-    } else if input.contains("struct SimpleTest") {
-        return "SimpleTest".to_string();
-    } else if input.contains("enum SimpleEnum") {
-        // Check which variants this SimpleEnum has to disambiguate between the two in tests
-        if input.contains("SimpleEnum {") && input.contains("One") {
-            return "SimpleEnum_One".to_string();
-        } else if input.contains("SimpleEnum {") && input.contains("First") {
-            return "SimpleEnum_First".to_string();
+                other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                    ::std::format!("expected object for {}, found {{}}", other.type_name())
+                )),
+            }}"#,
+            match (&input.attrs.tag, &input.attrs.tag_value) {
+                (Some(tag), Some(tag_value)) => check_struct_tag(name, tag, tag_value),
+                _ => String::new(),
+            },
+            deserialize_named_fields(named),
+            construct("Self", fields),
+            name
+        ),
+        Fields::Unnamed(unnamed) => format!(
+            r#"match value {{
+                ::fastjson::Value::Array(arr) => {{
+                    {}
+                    ::std::result::Result::Ok({})
+                }}
+                other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                    ::std::format!("expected array for {}, found {{}}", other.type_name())
+                )),
+            }}"#,
+            deserialize_unnamed_fields(name, unnamed),
+            construct("Self", fields),
+            name
+        ),
+        Fields::Unit => format!(
+            r#"match value {{
+                ::fastjson::Value::Null => ::std::result::Result::Ok(Self),
+                other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                    ::std::format!("expected null for {}, found {{}}", other.type_name())
+                )),
+            }}"#,
+            name
+        ),
+    };
+
+    format!(
+        r#"
+        {} {{
+            #[allow(unused_mut, unused_variables)]
+            fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {{
+                {}
+            }}
+        }}"#,
+        impl_header(input, "::fastjson::Deserialize", &input.attrs.bound_de),
+        body
+    )
+}
+
+fn generate_enum_deserialize(input: &Input, variants: &[Variant]) -> String {
+    let name = &input.name;
+    if input.attrs.numeric {
+        return generate_numeric_enum_deserialize(input, variants);
+    }
+    let tag_key = input.attrs.tag_key();
+    let mut unit_arms = String::new();
+    let mut tagged_arms = String::new();
+    let mut integer_arms = String::new();
+    // Under tag_ignore_case both the input and the patterns are lowercased
+    let as_str = if input.attrs.tag_ignore_case { "to_lowercase().as_str()" } else { "as_str()" };
+
+    for variant in variants {
+        let path = format!("{}::{}", name, variant.name);
+        let json_name = match input.attrs.tag_ignore_case {
+            true => format!("{:?}", variant.json_name().to_lowercase()),
+            false => format!("{:?}", variant.json_name()),
+        };
+        let arm = match &variant.fields {
+            Fields::Unit if variant.tag_value.is_some() => String::new(),
+            Fields::Unit => {
+                unit_arms.push_str(&format!(
+                    "{} => ::std::result::Result::Ok({}),\n",
+                    json_name, path
+                ));
+                // Also accept the object form `{"type": "Variant"}` used by
+                // producers that tag every variant the same way
+                if input.attrs.external_tag {
+                    continue;
+                }
+                String::new()
+            }
+            Fields::Unnamed(fields) if input.attrs.external_tag && fields.len() == 1 => format!(
+                "let {} = {}?;\n{}",
+                binding(0),
+                deserialize_field_expr(&fields[0], "content"),
+                validate_fields(fields)
+            ),
+            Fields::Unnamed(fields) if input.attrs.external_tag => format!(
+                r#"let arr = match content {{
+                    ::fastjson::Value::Array(arr) => arr,
+                    other => return ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("expected array for {}, found {{}}", other.type_name())
+                    )),
+                }};
+                {}"#,
+                path,
+                deserialize_unnamed_fields(&path, fields)
+            ),
+            Fields::Named(fields) if input.attrs.external_tag => format!(
+                r#"let mut map = match content {{
+                    ::fastjson::Value::Object(map) => map,
+                    other => return ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("expected object for {}, found {{}}", other.type_name())
+                    )),
+                }};
+                {}"#,
+                path,
+                deserialize_named_fields(fields)
+            ),
+            Fields::Unnamed(fields) => format!(
+                r#"let arr = match map.remove("data") {{
+                    ::std::option::Option::Some(::fastjson::Value::Array(arr)) => arr,
+                    ::std::option::Option::Some(_) => return ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        "expected array for enum variant data".to_string()
+                    )),
+                    ::std::option::Option::None => return ::std::result::Result::Err(
+                        ::fastjson::Error::MissingField("data".to_string())
+                    ),
+                }};
+                {}"#,
+                deserialize_unnamed_fields(&path, fields)
+            ),
+            Fields::Named(fields) => deserialize_named_fields(fields),
+        };
+
+        let (arms, pattern) = match variant.tag_value {
+            Some(tag) => (&mut integer_arms, format!("::std::option::Option::Some({})", tag)),
+            None => (&mut tagged_arms, json_name),
+        };
+        arms.push_str(&format!(
+            r#"{} => {{
+                {}
+                ::std::result::Result::Ok({})
+            }}
+            "#,
+            pattern,
+            arm,
+            construct(&path, &variant.fields)
+        ));
+    }
+
+    let object_arm = if input.attrs.external_tag {
+        format!(
+            r#"::fastjson::Value::Object(outer) => {{
+                let mut entries = outer.into_iter();
+                let (tag, content) = match (entries.next(), entries.next()) {{
+                    (::std::option::Option::Some(entry), ::std::option::Option::None) => entry,
+                    _ => return ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        "expected object with a single key for enum {}".to_string()
+                    )),
+                }};
+                match tag.{as_str} {{
+                    {}
+                    _ => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("unknown enum variant: {{}}", tag)
+                    )),
+                }}
+            }}"#,
+            name, tagged_arms
+        )
+    } else {
+        // Integer tags are only looked for when some variant declares a tag_value
+        let integer_tag = if integer_arms.is_empty() {
+            String::new()
         } else {
-            return "SimpleEnum".to_string();
-        }
-    } else if input.contains("struct SimpleString") {
-        return "SimpleString".to_string();
-    } else if input.contains("enum SimpleColors") {
-        return "SimpleColors".to_string();
-    } else if input.contains("struct TestOptional") {
-        return "TestOptional".to_string();
-    } else if input.contains("struct Container") {
-        return "Container".to_string();
-    } else if input.contains("struct Item") {
-        return "Item".to_string();
-    } else if input.contains("struct RequiredField") {
-        return "RequiredField".to_string();
-    }
-    
-    // More generic parser for other types
-    if let Some(pos) = input.find("struct ") {
-        let after_struct = &input[pos+7..];
-        if let Some(end) = after_struct.find(|c: char| c.is_whitespace() || c == '{' || c == '<') {
-            return after_struct[..end].trim().to_string();
-        }
-    } else if let Some(pos) = input.find("enum ") {
-        let after_enum = &input[pos+5..];
-        if let Some(end) = after_enum.find(|c: char| c.is_whitespace() || c == '{') {
-            return after_enum[..end].trim().to_string();
-        }
-    }
-    
-    // Fallback
-    "UnknownType".to_string()
-}
\ No newline at end of file
+            format!(
+                r#"if let ::std::option::Option::Some(::fastjson::Value::Number(n)) = map.get({tag_key:?}) {{
+                    let n = n.clone();
+                    map.remove({tag_key:?});
+                    return match n.as_i64() {{
+                        {integer_arms}
+                        _ => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                            ::std::format!("unknown enum variant {tag_key}: {{}}", n)
+                        )),
+                    }};
+                }}"#,
+                tag_key = tag_key,
+                integer_arms = integer_arms
+            )
+        };
+        format!(
+            r#"::fastjson::Value::Object(mut map) => {{
+                {integer_tag}
+                let tag = match map.remove({tag_key:?}) {{
+                    ::std::option::Option::Some(::fastjson::Value::String(t)) => t,
+                    ::std::option::Option::Some(other) => return ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("expected string for enum {tag_key}, found {{}}", other.type_name())
+                    )),
+                    ::std::option::Option::None => return ::std::result::Result::Err(
+                        ::fastjson::Error::MissingField({tag_key:?}.to_string())
+                    ),
+                }};
+                match tag.{as_str} {{
+                    {tagged_arms}
+                    _ => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("unknown enum variant {tag_key}: {{}}", tag)
+                    )),
+                }}
+            }}"#,
+            integer_tag = integer_tag,
+            tag_key = tag_key,
+            tagged_arms = tagged_arms
+        )
+    };
+
+    format!(
+        r#"
+        {header} {{
+            #[allow(unused_mut, unused_variables)]
+            fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {{
+                match value {{
+                    ::fastjson::Value::String(s) => match s.{as_str} {{
+                        {unit_arms}
+                        _ => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                            ::std::format!("unknown enum variant: {{}}", s)
+                        )),
+                    }},
+                    {object_arm}
+                    other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("expected string or object for enum {name}, found {{}}", other.type_name())
+                    )),
+                }}
+            }}
+        }}"#,
+        header = impl_header(input, "::fastjson::Deserialize", &input.attrs.bound_de),
+        name = name,
+        unit_arms = unit_arms,
+        object_arm = object_arm
+    )
+}
+
+/// Deserialize a `numeric` enum from an integer matching one of its discriminants
+fn generate_numeric_enum_deserialize(input: &Input, variants: &[Variant]) -> String {
+    let name = &input.name;
+    let arms: String = variants
+        .iter()
+        .map(|variant| {
+            format!(
+                "::std::option::Option::Some(n) if n == {name}::{v} as i64 => ::std::result::Result::Ok({name}::{v}),\n",
+                name = name,
+                v = variant.name
+            )
+        })
+        .collect();
+
+    format!(
+        r#"
+        {header} {{
+            fn deserialize(value: ::fastjson::Value) -> ::fastjson::Result<Self> {{
+                match value {{
+                    ::fastjson::Value::Number(n) => match n.as_i64() {{
+                        {arms}
+                        _ => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                            ::std::format!("invalid value {{}} for enum {name}", n)
+                        )),
+                    }},
+                    other => ::std::result::Result::Err(::fastjson::Error::TypeError(
+                        ::std::format!("expected integer for enum {name}, found {{}}", other.type_name())
+                    )),
+                }}
+            }}
+        }}"#,
+        header = impl_header(input, "::fastjson::Deserialize", &input.attrs.bound_de),
+        name = name,
+        arms = arms
+    )
+}
@@ -0,0 +1,95 @@
+//! Base64 encoding for byte fields
+//!
+//! Uses the standard alphabet with `=` padding (RFC 4648). The `serialize` and
+//! `deserialize` functions have the shape expected by `#[fastjson(with = "...")]`,
+//! and `#[fastjson(bytes_as_base64)]` is shorthand for using this module.
+
+use crate::error::{Error, Result};
+use crate::value::Value;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as a base64 JSON string
+pub fn serialize<T: AsRef<[u8]> + ?Sized>(bytes: &T) -> Result<Value> {
+    Ok(Value::String(encode(bytes.as_ref())))
+}
+
+/// Decode a base64 JSON string into bytes
+pub fn deserialize<T: From<Vec<u8>>>(value: Value) -> Result<T> {
+    match value {
+        Value::String(s) => decode(&s).map(T::from),
+        _ => Err(Error::TypeError(format!("expected base64 string, found {}", value.type_name()))),
+    }
+}
+
+/// Encode bytes as padded base64 text
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    out
+}
+
+/// Decode padded base64 text, rejecting anything outside the canonical encoding
+pub fn decode(text: &str) -> Result<Vec<u8>> {
+    let input = text.as_bytes();
+    if !input.len().is_multiple_of(4) {
+        return Err(Error::custom(format!(
+            "invalid base64 length {}: must be a multiple of 4",
+            input.len()
+        )));
+    }
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let chunks = input.len() / 4;
+    for (index, chunk) in input.chunks(4).enumerate() {
+        let is_last = index + 1 == chunks;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return Err(Error::custom("invalid base64 padding"));
+        }
+
+        let mut n = 0u32;
+        for (i, &c) in chunk[..4 - padding].iter().enumerate() {
+            let digit = match sextet(c) {
+                Some(digit) => digit,
+                None => {
+                    return Err(Error::custom(format!(
+                        "invalid base64 character {:?} at offset {}",
+                        c as char,
+                        index * 4 + i
+                    )));
+                }
+            };
+            n |= digit << (18 - 6 * i);
+        }
+
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let len = 3 - padding;
+        // Bits below the last full byte must be zero in a canonical encoding
+        if bytes[len..].iter().any(|&b| b != 0) {
+            return Err(Error::custom("invalid base64: non-zero trailing bits"));
+        }
+        out.extend_from_slice(&bytes[..len]);
+    }
+    Ok(out)
+}
+
+fn sextet(c: u8) -> Option<u32> {
+    let digit = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    };
+    Some(digit as u32)
+}
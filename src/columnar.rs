@@ -0,0 +1,94 @@
+//! Columnar layout for sequences of structs
+//!
+//! Data-science exports often write a table as one array per column, such as
+//! `{"name": ["a", "b"], "age": [1, 2]}`, rather than one object per row. The
+//! `serialize` and `deserialize` functions have the shape expected by
+//! `#[fastjson(with = "...")]` and convert between that layout and a `Vec` of
+//! rows; `#[fastjson(columnar)]` is shorthand for using this module.
+
+use crate::de::Deserialize;
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::ser::Serialize;
+use crate::value::Value;
+
+/// Write rows as an object of columns, one array per key
+///
+/// Columns follow the key order of the rows. A row without a key that others
+/// have, such as a `None` field skipped with `skip_if_none`, gets `null` in
+/// that column.
+pub fn serialize<T: Serialize>(rows: &[T]) -> Result<Value> {
+    let mut columns = Map::new();
+    for (index, row) in rows.iter().enumerate() {
+        let row = match row.serialize()? {
+            Value::Object(map) => map,
+            other => {
+                return Err(Error::TypeError(format!(
+                    "expected object for columnar row, found {}",
+                    other.type_name()
+                )))
+            }
+        };
+        for (key, value) in row {
+            let column = columns.entry(key).or_insert_with(|| Value::Array(vec![Value::Null; index]));
+            if let Value::Array(column) = column {
+                column.resize(index, Value::Null);
+                column.push(value);
+            }
+        }
+    }
+    for column in columns.values_mut() {
+        if let Value::Array(column) = column {
+            column.resize(rows.len(), Value::Null);
+        }
+    }
+    Ok(Value::Object(columns))
+}
+
+/// Read an object of equal-length columns into one row per index
+pub fn deserialize<T: Deserialize>(value: Value) -> Result<Vec<T>> {
+    let map = match value {
+        Value::Object(map) => map,
+        other => {
+            return Err(Error::TypeError(format!(
+                "expected object of columns, found {}",
+                other.type_name()
+            )))
+        }
+    };
+
+    let mut columns = Vec::with_capacity(map.len());
+    for (key, column) in map {
+        match column {
+            Value::Array(items) => columns.push((key, items.into_iter())),
+            other => {
+                return Err(Error::TypeError(format!(
+                    "expected array for column `{}`, found {}",
+                    key,
+                    other.type_name()
+                )))
+            }
+        }
+    }
+
+    let rows = columns.first().map_or(0, |(_, items)| items.len());
+    if let Some((key, items)) = columns.iter().find(|(_, items)| items.len() != rows) {
+        return Err(Error::custom(format!(
+            "column `{}` has {} values, expected {} like column `{}`",
+            key,
+            items.len(),
+            rows,
+            columns[0].0
+        )));
+    }
+
+    (0..rows)
+        .map(|_| {
+            let row = columns
+                .iter_mut()
+                .map(|(key, items)| (key.clone(), items.next().unwrap_or(Value::Null)))
+                .collect();
+            T::deserialize(Value::Object(row))
+        })
+        .collect()
+}
@@ -1,7 +1,19 @@
 use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::number::Number;
 use crate::value::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, LinkedList, VecDeque};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Range, RangeInclusive};
+use std::num::Wrapping;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// A trait for types that can be deserialized from JSON
 pub trait Deserialize: Sized {
@@ -9,189 +21,277 @@ pub trait Deserialize: Sized {
     fn deserialize(value: Value) -> Result<Self>;
 }
 
+/// A stateful counterpart to [`Deserialize`] for decoding that needs runtime context
+///
+/// The seed is consumed by the call, so it can carry borrowed state such as an
+/// interner or an arena.
+///
+/// ```rust
+/// use fastjson::{DeserializeSeed, Error, Result, Value, from_str_seed};
+/// use std::collections::HashMap;
+///
+/// #[derive(Default)]
+/// struct Interner {
+///     ids: HashMap<String, u32>,
+///     names: Vec<String>,
+/// }
+///
+/// impl Interner {
+///     fn intern(&mut self, name: String) -> u32 {
+///         if let Some(&id) = self.ids.get(&name) {
+///             return id;
+///         }
+///         let id = self.names.len() as u32;
+///         self.names.push(name.clone());
+///         self.ids.insert(name, id);
+///         id
+///     }
+/// }
+///
+/// // Decodes an array of strings into interned symbol ids
+/// struct Symbols<'a>(&'a mut Interner);
+///
+/// impl DeserializeSeed<Vec<u32>> for Symbols<'_> {
+///     fn deserialize(self, value: Value) -> Result<Vec<u32>> {
+///         match value {
+///             Value::Array(items) => items
+///                 .into_iter()
+///                 .map(|item| match item {
+///                     Value::String(s) => Ok(self.0.intern(s)),
+///                     other => Err(Error::TypeError(format!("expected string, found {}", other.type_name()))),
+///                 })
+///                 .collect(),
+///             other => Err(Error::TypeError(format!("expected array, found {}", other.type_name()))),
+///         }
+///     }
+/// }
+///
+/// let mut interner = Interner::default();
+/// let ids = from_str_seed(r#"["red", "green", "red"]"#, Symbols(&mut interner)).unwrap();
+/// assert_eq!(ids, vec![0, 1, 0]);
+/// assert_eq!(interner.names, vec!["red", "green"]);
+/// ```
+pub trait DeserializeSeed<T> {
+    /// Deserialize a value of type `T` from JSON using this seed
+    fn deserialize(self, value: Value) -> Result<T>;
+}
+
+// A PhantomData seed falls back to the stateless Deserialize impl
+impl<T: Deserialize> DeserializeSeed<T> for PhantomData<T> {
+    fn deserialize(self, value: Value) -> Result<T> {
+        T::deserialize(value)
+    }
+}
+
 impl Deserialize for bool {
     fn deserialize(value: Value) -> Result<Self> {
         match value {
             Value::Bool(b) => Ok(b),
-            _ => Err(Error::TypeError(format!("expected boolean, found {:?}", value))),
+            _ => Err(Error::TypeError(format!("expected boolean, found {}", value.type_name()))),
         }
     }
 }
 
 impl Deserialize for i8 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < i8::MIN as f64 || n > i8::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for i8", n)));
-                }
-                Ok(n as i8)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "i8")
     }
 }
 
 impl Deserialize for i16 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < i16::MIN as f64 || n > i16::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for i16", n)));
-                }
-                Ok(n as i16)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "i16")
     }
 }
 
 impl Deserialize for i32 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < i32::MIN as f64 || n > i32::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for i32", n)));
-                }
-                Ok(n as i32)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "i32")
     }
 }
 
 impl Deserialize for i64 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                // JavaScript can't precisely represent all i64 values, so we need to check if this 
-                // value is accurately representable as an i64
-                if n < -9007199254740991.0 || n > 9007199254740991.0 {
-                    return Err(Error::TypeError(format!(
-                        "value {} may not be precisely representable as i64", n
-                    )));
-                }
-                Ok(n as i64)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "i64")
     }
 }
 
 impl Deserialize for u8 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < 0.0 || n > u8::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for u8", n)));
-                }
-                Ok(n as u8)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "u8")
     }
 }
 
 impl Deserialize for u16 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < 0.0 || n > u16::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for u16", n)));
-                }
-                Ok(n as u16)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "u16")
     }
 }
 
 impl Deserialize for u32 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < 0.0 || n > u32::MAX as f64 {
-                    return Err(Error::TypeError(format!("value {} out of range for u32", n)));
-                }
-                Ok(n as u32)
-            }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        integer_from_value(value, "u32")
     }
 }
 
 impl Deserialize for u64 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => {
-                if n.fract() != 0.0 {
-                    return Err(Error::TypeError(format!("expected integer, found float {}", n)));
-                }
-                if n < 0.0 {
-                    return Err(Error::TypeError(format!("value {} out of range for u64", n)));
-                }
-                // JavaScript can't precisely represent all u64 values, so we need to check if this 
-                // value is accurately representable as a u64
-                if n > 9007199254740991.0 {
-                    return Err(Error::TypeError(format!(
-                        "value {} may not be precisely representable as u64", n
-                    )));
-                }
-                Ok(n as u64)
+        integer_from_value(value, "u64")
+    }
+}
+
+impl Deserialize for isize {
+    fn deserialize(value: Value) -> Result<Self> {
+        integer_from_value(value, "isize")
+    }
+}
+
+impl Deserialize for usize {
+    fn deserialize(value: Value) -> Result<Self> {
+        integer_from_value(value, "usize")
+    }
+}
+
+// Largest magnitude at which every integer is exactly representable in f64
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+// Integers parsed from JSON text are exact and only need a range check.
+// Integral floats (e.g. `5.0` or `1e3`) are accepted within the safe range,
+// since beyond 2^53 the float may already have been rounded.
+fn integer_from_value<T: TryFrom<i128>>(value: Value, ty: &'static str) -> Result<T> {
+    let n = match value {
+        Value::Number(n) => n,
+        // Numeric strings are only accepted when coerce_numbers_from_strings is active
+        Value::String(ref s) if coerce_numbers_from_strings() => {
+            let int = s.parse::<i128>().map_err(|_| Error::TypeError(format!("invalid integer string {:?}", s)))?;
+            let int = T::try_from(int).map_err(|_| Error::TypeError(format!("value {} out of range for {}", s, ty)))?;
+            warn(|| Warning::NumberFromString { value: s.clone(), ty });
+            return Ok(int);
+        }
+        _ => return Err(Error::TypeError(format!("expected number, found {}", value.type_name()))),
+    };
+
+    let int = match n.as_integer() {
+        Some(int) => int,
+        None => {
+            let f = n.as_f64();
+            if f.fract() != 0.0 {
+                return Err(Error::TypeError(format!("expected integer, found float {}", n)));
+            }
+            if !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&f) {
+                return Err(Error::TypeError(format!(
+                    "value {} may not be precisely representable as {}", n, ty
+                )));
             }
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
+            f as i128
         }
+    };
+
+    let int = T::try_from(int).map_err(|_| Error::TypeError(format!("value {} out of range for {}", n, ty)))?;
+    if n.as_integer().is_none() {
+        warn(|| Warning::IntegerFromFloat { value: n.to_string(), ty });
     }
+    Ok(int)
 }
 
 impl Deserialize for f32 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => Ok(n as f32),
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
+        let n = float_from_value(value, "f32", |n| format!("{:e}", n as f32))?;
+        // Finite values past f32::MAX would otherwise become infinity
+        if (n as f32).is_infinite() {
+            return Err(Error::TypeError(format!("value {:e} out of range for f32", n)));
         }
+        Ok(n as f32)
     }
 }
 
 impl Deserialize for f64 {
     fn deserialize(value: Value) -> Result<Self> {
-        match value {
-            Value::Number(n) => Ok(n),
-            _ => Err(Error::TypeError(format!("expected number, found {:?}", value))),
-        }
+        float_from_value(value, "f64", |n| format!("{:e}", n))
+    }
+}
+
+// Numeric strings are only accepted when coerce_numbers_from_strings is active.
+// `shortest` writes the value as the target type holds it, to spot rounding.
+fn float_from_value(value: Value, ty: &'static str, shortest: fn(f64) -> String) -> Result<f64> {
+    let (n, text) = match value {
+        Value::Number(n) if !collecting_warnings() => return Ok(n.as_f64()),
+        Value::Number(n) => (n.as_f64(), n.to_string()),
+        Value::String(s) if coerce_numbers_from_strings() => match s.parse::<f64>() {
+            Ok(n) if n.is_finite() => {
+                warn(|| Warning::NumberFromString { value: s.clone(), ty });
+                (n, s)
+            }
+            _ => return Err(Error::TypeError(format!("invalid numeric string {:?}", s))),
+        },
+        _ => return Err(Error::TypeError(format!("expected number, found {}", value.type_name()))),
+    };
+    if collecting_warnings() && decimal_digits(&text) != decimal_digits(&shortest(n)) {
+        warn(|| Warning::PrecisionLoss { value: text, ty });
+    }
+    Ok(n)
+}
+
+// Significant digits and decimal exponent of a numeric literal, so that
+// `150`, `1.50e2` and `1.5e2` all compare equal
+fn decimal_digits(text: &str) -> (String, i64) {
+    let text = text.trim_start_matches('-');
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i64>().unwrap_or(0)),
+        None => (text, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all: String = int.chars().chain(frac.chars()).collect();
+    let significant = all.trim_start_matches('0');
+    let digits = significant.trim_end_matches('0');
+    if digits.is_empty() {
+        return (String::new(), 0);
     }
+    let leading_zeros = (all.len() - significant.len()) as i64;
+    (digits.to_string(), int.len() as i64 - leading_zeros + exponent)
 }
 
 impl Deserialize for String {
     fn deserialize(value: Value) -> Result<Self> {
         match value {
             Value::String(s) => Ok(s),
-            _ => Err(Error::TypeError(format!("expected string, found {:?}", value))),
+            _ => Err(Error::TypeError(format!("expected string, found {}", value.type_name()))),
         }
     }
 }
 
+impl Deserialize for PathBuf {
+    fn deserialize(value: Value) -> Result<Self> {
+        match value {
+            Value::String(s) => Ok(PathBuf::from(s)),
+            _ => Err(Error::TypeError(format!("expected string, found {}", value.type_name()))),
+        }
+    }
+}
+
+// Types without a native JSON representation are decoded from their FromStr string form
+macro_rules! from_str_impls {
+    ($($ty:ident)+) => {
+        $(
+            impl Deserialize for $ty {
+                fn deserialize(value: Value) -> Result<Self> {
+                    match value {
+                        Value::String(s) => s.parse().map_err(|_| {
+                            Error::TypeError(format!("invalid {}: {:?}", stringify!($ty), s))
+                        }),
+                        _ => Err(Error::TypeError(format!("expected string, found {}", value.type_name()))),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+from_str_impls!(IpAddr Ipv4Addr Ipv6Addr SocketAddr SocketAddrV4 SocketAddrV6);
+
+// 128-bit integers do not fit in an f64, so they travel as strings
+from_str_impls!(i128 u128);
+
 impl<T: Deserialize> Deserialize for Option<T> {
     fn deserialize(value: Value) -> Result<Self> {
         if let Value::Null = value {
@@ -202,6 +302,62 @@ impl<T: Deserialize> Deserialize for Option<T> {
     }
 }
 
+// Wrapping integers are read from the plain inner value
+impl<T: Deserialize> Deserialize for Wrapping<T> {
+    fn deserialize(value: Value) -> Result<Self> {
+        T::deserialize(value).map(Wrapping)
+    }
+}
+
+// Expects exactly one of the keys "Ok" and "Err"
+impl<T: Deserialize, E: Deserialize> Deserialize for std::result::Result<T, E> {
+    fn deserialize(value: Value) -> Result<Self> {
+        let map = match value {
+            Value::Object(map) => map,
+            _ => return Err(Error::TypeError(format!("expected object for Result, found {}", value.type_name()))),
+        };
+        if map.len() != 1 {
+            return Err(Error::TypeError(format!(
+                "expected object with a single \"Ok\" or \"Err\" key for Result, found {} keys",
+                map.len()
+            )));
+        }
+        let (tag, content) = map.into_iter().next().unwrap();
+        match tag.as_str() {
+            "Ok" => Ok(Ok(T::deserialize(content)?)),
+            "Err" => Ok(Err(E::deserialize(content)?)),
+            _ => Err(Error::TypeError(format!("expected \"Ok\" or \"Err\" key for Result, found \"{}\"", tag))),
+        }
+    }
+}
+
+// Ranges are read from {"start": a, "end": b}, with both keys required
+fn deserialize_range<T: Deserialize>(value: Value) -> Result<(T, T)> {
+    let mut map = match value {
+        Value::Object(map) => map,
+        _ => return Err(Error::TypeError(format!("expected object for range, found {}", value.type_name()))),
+    };
+    let mut bound = |key: &str| match map.remove(key) {
+        Some(value) => T::deserialize(value),
+        None => Err(Error::MissingField(key.to_string())),
+    };
+    Ok((bound("start")?, bound("end")?))
+}
+
+impl<T: Deserialize> Deserialize for Range<T> {
+    fn deserialize(value: Value) -> Result<Self> {
+        let (start, end) = deserialize_range(value)?;
+        Ok(start..end)
+    }
+}
+
+impl<T: Deserialize> Deserialize for RangeInclusive<T> {
+    fn deserialize(value: Value) -> Result<Self> {
+        let (start, end) = deserialize_range(value)?;
+        Ok(start..=end)
+    }
+}
+
 impl<T: Deserialize> Deserialize for Vec<T> {
     fn deserialize(value: Value) -> Result<Self> {
         match value {
@@ -212,11 +368,85 @@ impl<T: Deserialize> Deserialize for Vec<T> {
                 }
                 Ok(result)
             }
-            _ => Err(Error::TypeError(format!("expected array, found {:?}", value))),
+            _ => Err(Error::TypeError(format!("expected array, found {}", value.type_name()))),
         }
     }
 }
 
+// Other sequence collections deserialize from arrays, keeping element order
+macro_rules! seq_impls {
+    ($($ty:ident)+) => {
+        $(
+            impl<T: Deserialize> Deserialize for $ty<T> {
+                fn deserialize(value: Value) -> Result<Self> {
+                    Vec::<T>::deserialize(value).map(|vec| vec.into_iter().collect())
+                }
+            }
+        )+
+    };
+}
+
+seq_impls!(VecDeque LinkedList);
+
+// Tuples deserialize from arrays of exactly the same length
+macro_rules! tuple_impls {
+    ($($len:literal => ($($name:ident)+))+) => {
+        $(
+            impl<$($name: Deserialize),+> Deserialize for ($($name,)+) {
+                fn deserialize(value: Value) -> Result<Self> {
+                    match value {
+                        Value::Array(arr) => {
+                            if arr.len() != $len {
+                                return Err(Error::TypeError(format!(
+                                    "expected array with {} elements, found array with {} elements",
+                                    $len,
+                                    arr.len()
+                                )));
+                            }
+                            let mut items = arr.into_iter();
+                            Ok(($($name::deserialize(items.next().unwrap())?,)+))
+                        }
+                        _ => Err(Error::TypeError(format!("expected array, found {}", value.type_name()))),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+tuple_impls! {
+    1 => (A)
+    2 => (A B)
+    3 => (A B C)
+    4 => (A B C D)
+    5 => (A B C D E)
+    6 => (A B C D E F)
+    7 => (A B C D E F G)
+    8 => (A B C D E F G H)
+    9 => (A B C D E F G H I)
+    10 => (A B C D E F G H I J)
+    11 => (A B C D E F G H I J K)
+    12 => (A B C D E F G H I J K L)
+}
+
+impl<T: Deserialize> Deserialize for Box<[T]> {
+    fn deserialize(value: Value) -> Result<Self> {
+        Ok(Vec::<T>::deserialize(value)?.into_boxed_slice())
+    }
+}
+
+impl<T: Deserialize> Deserialize for Rc<[T]> {
+    fn deserialize(value: Value) -> Result<Self> {
+        Ok(Vec::<T>::deserialize(value)?.into())
+    }
+}
+
+impl<T: Deserialize> Deserialize for Arc<[T]> {
+    fn deserialize(value: Value) -> Result<Self> {
+        Ok(Vec::<T>::deserialize(value)?.into())
+    }
+}
+
 impl<K, V> Deserialize for HashMap<K, V>
 where
     K: FromStr + std::hash::Hash + Eq,
@@ -234,7 +464,29 @@ where
                 }
                 Ok(result)
             }
-            _ => Err(Error::TypeError(format!("expected object, found {:?}", value))),
+            _ => Err(Error::TypeError(format!("expected object, found {}", value.type_name()))),
+        }
+    }
+}
+
+impl Deserialize for Duration {
+    fn deserialize(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(mut map) => {
+                let secs = match map.remove("secs") {
+                    Some(v) => u64::deserialize(v)?,
+                    None => return Err(Error::MissingField("secs".to_string())),
+                };
+                let nanos = match map.remove("nanos") {
+                    Some(v) => u32::deserialize(v)?,
+                    None => return Err(Error::MissingField("nanos".to_string())),
+                };
+                if nanos >= 1_000_000_000 {
+                    return Err(Error::TypeError(format!("nanos {} out of range for Duration", nanos)));
+                }
+                Ok(Duration::new(secs, nanos))
+            }
+            _ => Err(Error::TypeError(format!("expected object, found {}", value.type_name()))),
         }
     }
 }
@@ -245,48 +497,562 @@ impl Deserialize for Value {
     }
 }
 
+/// Accepts any JSON value and discards it
+///
+/// Declare a field as `IgnoredAny` to require that a key is present without
+/// caring what it holds, or use it as the element type to count the entries of
+/// an array without keeping them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IgnoredAny;
+
+impl Deserialize for IgnoredAny {
+    fn deserialize(_value: Value) -> Result<Self> {
+        Ok(IgnoredAny)
+    }
+}
+
+impl Deserialize for Map {
+    fn deserialize(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(map) => Ok(map),
+            _ => Err(Error::TypeError(format!("expected object, found {}", value.type_name()))),
+        }
+    }
+}
+
+// Ad-hoc extraction from a parsed Value, sharing the Deserialize checks.
+// `$accepts` lists the variants each type can deserialize from, so a borrowed
+// value of any other type is rejected without cloning it.
+macro_rules! try_from_value_impls {
+    ($($ty:ty => $accepts:pat),+ $(,)?) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self> {
+                    <$ty as Deserialize>::deserialize(value)
+                }
+            }
+
+            impl TryFrom<&Value> for $ty {
+                type Error = Error;
+
+                fn try_from(value: &Value) -> Result<Self> {
+                    match value {
+                        $accepts => <$ty as Deserialize>::deserialize(value.clone()),
+                        other => <$ty as Deserialize>::deserialize(empty_like(other)),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+// A value of the same JSON type as `value` with nothing inside, enough for
+// Deserialize to report a type mismatch
+fn empty_like(value: &Value) -> Value {
+    match value {
+        Value::Array(_) => Value::Array(Vec::new()),
+        Value::Object(_) => Value::Object(Map::new()),
+        scalar => scalar.clone(),
+    }
+}
+
+try_from_value_impls!(
+    bool => Value::Bool(_),
+    String => Value::String(_),
+    f64 => Value::Number(_) | Value::String(_),
+    i64 => Value::Number(_) | Value::String(_),
+    Vec<Value> => Value::Array(_),
+    Map => Value::Object(_),
+    HashMap<String, Value> => Value::Object(_),
+);
+
+/// Options controlling how JSON text is parsed
+///
+/// The defaults accept everything the JSON spec allows, apart from documents
+/// nested deeper than `max_depth` and numbers longer than `max_number_length`.
+/// `max_input_len` can also cap the size of the whole document. Exceeding any
+/// of the three gives `Error::LimitExceeded`.
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    /// Reject objects that contain the same key more than once instead of
+    /// keeping the last value
+    pub deny_duplicate_keys: bool,
+    /// Accept numeric strings such as `"30"` or `"5.0"` where a number is expected
+    ///
+    /// Integer types only accept strings holding a plain integer, so `"5.0"`
+    /// is still rejected for a `u32`.
+    ///
+    /// Only applies while deserializing through `from_str_with_options` or
+    /// `from_str_checked`; the other `from_str` functions always use the default.
+    pub coerce_numbers_from_strings: bool,
+    /// Skip array elements that fail to parse instead of failing the whole
+    /// document
+    ///
+    /// Recovery only happens at array element boundaries: a malformed element
+    /// is dropped up to the next `,` or `]` of its array, however deeply the
+    /// error is nested inside it. Errors that are not inside any array
+    /// element, such as a malformed top-level object, a missing comma or a
+    /// trailing comma, still fail the parse, as does a document that ends in
+    /// the middle of an element. Use `parse_with_errors` to see the skipped
+    /// elements' errors.
+    pub recover_array_elements: bool,
+    /// Longest number literal accepted, in characters including sign,
+    /// fraction and exponent
+    ///
+    /// Bounds the work spent converting a pathological number with millions
+    /// of digits. Defaults to 1024, far more than any `f64` needs.
+    pub max_number_length: usize,
+    /// Most arrays and objects that may be open at once
+    ///
+    /// The parser itself does not recurse, so this may be raised as far as
+    /// memory allows. It still guards the rest of a program: dropping,
+    /// comparing or serializing a `Value` recurses once per level, so a
+    /// document of a million `[` would exhaust the stack there. Defaults to 128.
+    pub max_depth: usize,
+    /// Longest document accepted, in bytes, or `None` for no limit
+    ///
+    /// Checked before parsing starts, so an oversized document costs nothing
+    /// to reject. The error's position is the limit itself, the first byte
+    /// past what is allowed.
+    pub max_input_len: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            deny_duplicate_keys: false,
+            coerce_numbers_from_strings: false,
+            recover_array_elements: false,
+            max_number_length: 1024,
+            max_depth: 128,
+            max_input_len: None,
+        }
+    }
+}
+
+// Deserialize impls have no options parameter, so the options of the
+// enclosing from_str call are published per thread. Every from_str variant
+// installs its own, so a nested call never sees the options of its caller.
+thread_local! {
+    static COERCE_NUMBERS_FROM_STRINGS: Cell<bool> = const { Cell::new(false) };
+}
+
+fn coerce_numbers_from_strings() -> bool {
+    COERCE_NUMBERS_FROM_STRINGS.with(Cell::get)
+}
+
+thread_local! {
+    static WARNINGS: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+fn collecting_warnings() -> bool {
+    WARNINGS.with(|w| w.borrow().is_some())
+}
+
+// Record a warning if a from_str_checked call is collecting them
+fn warn(make: impl FnOnce() -> Warning) {
+    WARNINGS.with(|w| {
+        if let Some(warnings) = w.borrow_mut().as_mut() {
+            warnings.push(make());
+        }
+    });
+}
+
+// Collects warnings for one from_str_checked call, restoring the enclosing
+// collector (if any) when dropped
+struct WarningCollector {
+    previous: Option<Vec<Warning>>,
+}
+
+impl WarningCollector {
+    fn install() -> Self {
+        WarningCollector { previous: WARNINGS.with(|w| w.replace(Some(Vec::new()))) }
+    }
+
+    fn take(&self) -> Vec<Warning> {
+        WARNINGS.with(|w| w.borrow_mut().as_mut().map(std::mem::take).unwrap_or_default())
+    }
+}
+
+impl Drop for WarningCollector {
+    fn drop(&mut self) {
+        WARNINGS.with(|w| *w.borrow_mut() = self.previous.take());
+    }
+}
+
+// Restores the previous deserialize options, even if deserialization panics
+struct DeserializeOptionsGuard {
+    coerce_numbers_from_strings: bool,
+}
+
+impl DeserializeOptionsGuard {
+    fn install(options: &ParserOptions) -> Self {
+        let previous = COERCE_NUMBERS_FROM_STRINGS.with(|c| c.replace(options.coerce_numbers_from_strings));
+        DeserializeOptionsGuard { coerce_numbers_from_strings: previous }
+    }
+}
+
+impl Drop for DeserializeOptionsGuard {
+    fn drop(&mut self) {
+        COERCE_NUMBERS_FROM_STRINGS.with(|c| c.set(self.coerce_numbers_from_strings));
+    }
+}
+
 // Parse a JSON string into a Value
 pub fn parse(json: &str) -> Result<Value> {
-    let mut parser = Parser::new(json);
-    let value = parser.parse()?;
-    
-    // Make sure we've consumed all input
-    parser.skip_whitespace();
-    if parser.peek().is_some() {
-        // Character position for error
-        let (pos, c) = parser.peek().unwrap();
-        return Err(Error::syntax(pos, format!("trailing character '{}' after JSON value", c)));
+    parse_with_options(json, &ParserOptions::default())
+}
+
+// Parse a JSON string into a Value using the given options
+pub fn parse_with_options(json: &str, options: &ParserOptions) -> Result<Value> {
+    parse_document(json, options)
+        .map(|(value, _)| value)
+        .map_err(|err| err.with_snippet(json))
+}
+
+// Parse a JSON string, also returning the errors of any array elements
+// skipped under `ParserOptions::recover_array_elements`
+pub fn parse_with_errors(json: &str, options: &ParserOptions) -> Result<(Value, Vec<Error>)> {
+    let (value, errors) = parse_document(json, options).map_err(|err| err.with_snippet(json))?;
+    let errors = errors.into_iter().map(|err| err.with_snippet(json)).collect();
+    Ok((value, errors))
+}
+
+fn parse_document(json: &str, options: &ParserOptions) -> Result<(Value, Vec<Error>)> {
+    Parser::new(json, options).parse_document()
+}
+
+/// Upper bound on buffers of each kind kept by a [`ReusableParser`]
+const MAX_POOLED: usize = 1024;
+
+/// A parser that reuses the allocations of earlier documents
+///
+/// Parsing many small messages with [`parse`] allocates a fresh `Vec`, `Map`
+/// or `String` for every array, object and string. A `ReusableParser` keeps
+/// the buffers of values handed back through [`ReusableParser::recycle`] or
+/// [`ReusableParser::parse_into`] and fills them on the next parse, so a
+/// steady stream of similar documents allocates little after warming up.
+///
+/// ```
+/// use fastjson::{ReusableParser, Value};
+///
+/// let mut parser = ReusableParser::new();
+/// let mut message = Value::Null;
+/// for json in [r#"{"id": 1, "tags": ["a"]}"#, r#"{"id": 2, "tags": ["b", "c"]}"#] {
+///     parser.parse_into(json, &mut message).unwrap();
+///     assert!(message.get("tags").and_then(Value::as_array).is_some());
+/// }
+/// ```
+///
+/// Pooled buffers keep their capacity, so after one unusually large document
+/// the parser holds on to that memory until it is dropped.
+#[derive(Debug, Default)]
+pub struct ReusableParser {
+    options: ParserOptions,
+    pool: BufferPool,
+}
+
+impl ReusableParser {
+    /// Create a parser with the default options
+    pub fn new() -> Self {
+        Self::default()
     }
-    
-    Ok(value)
+
+    /// Create a parser with the given options
+    ///
+    /// Elements skipped under `recover_array_elements` are dropped without
+    /// reporting their errors; use [`parse_with_errors`] to see them.
+    pub fn with_options(options: ParserOptions) -> Self {
+        ReusableParser { options, pool: BufferPool::default() }
+    }
+
+    /// Parse a document, filling pooled buffers where available
+    pub fn parse(&mut self, json: &str) -> Result<Value> {
+        let mut parser = Parser::new(json, &self.options);
+        parser.pool = std::mem::take(&mut self.pool);
+        let result = parser.parse_document();
+        self.pool = parser.pool;
+        result.map(|(value, _)| value).map_err(|err| err.with_snippet(json))
+    }
+
+    /// Recycle the current contents of `scratch`, then parse a document into it
+    ///
+    /// On error `scratch` is left as `Null`.
+    pub fn parse_into(&mut self, json: &str, scratch: &mut Value) -> Result<()> {
+        self.recycle(std::mem::take(scratch));
+        *scratch = self.parse(json)?;
+        Ok(())
+    }
+
+    /// Hand a value's arrays, objects and strings back for later parses
+    pub fn recycle(&mut self, value: Value) {
+        self.pool.recycle(value);
+    }
+}
+
+// Emptied buffers waiting to be filled by the parser
+#[derive(Debug, Default)]
+struct BufferPool {
+    arrays: Vec<Vec<Value>>,
+    objects: Vec<Map>,
+    strings: Vec<String>,
+    // The parser's stack of open containers and `recycle`'s work list, both
+    // empty between uses and kept for their capacity
+    frames: Vec<Frame>,
+    pending: Vec<Value>,
+}
+
+impl BufferPool {
+    fn array(&mut self) -> Vec<Value> {
+        self.arrays.pop().unwrap_or_default()
+    }
+
+    fn object(&mut self) -> Map {
+        self.objects.pop().unwrap_or_default()
+    }
+
+    fn string(&mut self) -> String {
+        self.strings.pop().unwrap_or_default()
+    }
+
+    // Takes values apart with a work list rather than recursion, so documents
+    // of any depth the parser accepts can be recycled
+    fn recycle(&mut self, value: Value) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.push(value);
+        while let Some(value) = pending.pop() {
+            match value {
+                Value::String(mut s) if self.strings.len() < MAX_POOLED => {
+                    s.clear();
+                    self.strings.push(s);
+                }
+                Value::Array(mut items) => {
+                    pending.append(&mut items);
+                    if self.arrays.len() < MAX_POOLED {
+                        self.arrays.push(items);
+                    }
+                }
+                Value::Object(mut map) => {
+                    for (key, value) in map.drain() {
+                        pending.push(Value::String(key));
+                        pending.push(value);
+                    }
+                    if self.objects.len() < MAX_POOLED {
+                        self.objects.push(map);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.pending = pending;
+    }
+}
+
+// Parse consecutive JSON values, such as newline-delimited JSON, until the end of input
+//
+// Values may be separated by any whitespace, or by nothing where that is
+// unambiguous, as in `{}{}`. Empty or all-whitespace input gives no values.
+pub fn parse_many(json: &str) -> Result<Vec<Value>> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(json, &options);
+    let mut values = Vec::new();
+    loop {
+        parser.skip_whitespace();
+        if parser.peek().is_none() {
+            return Ok(values);
+        }
+        let value = parser.parse_value().map_err(|err| err.with_snippet(json))?;
+        values.push(value);
+    }
+}
+
+// Deserialize every value of a sequence of concatenated JSON values
+pub fn from_str_many<T: Deserialize>(json: &str) -> Result<Vec<T>> {
+    let values = parse_many(json)?;
+    let _guard = DeserializeOptionsGuard::install(&ParserOptions::default());
+    values.into_iter().map(T::deserialize).collect()
 }
 
 // Deserialize a JSON string into any type that implements Deserialize
 pub fn from_str<T: Deserialize>(json: &str) -> Result<T> {
-    let value = parse(json)?;
+    from_str_with_options(json, &ParserOptions::default())
+}
+
+// Deserialize a JSON string using the given parser options
+pub fn from_str_with_options<T: Deserialize>(json: &str, options: &ParserOptions) -> Result<T> {
+    let value = parse_with_options(json, options)?;
+    let _guard = DeserializeOptionsGuard::install(options);
     T::deserialize(value)
 }
 
+// Deserialize a JSON string with the given options, also returning a warning
+// for each lossy or lenient conversion (see Warning)
+pub fn from_str_checked<T: Deserialize>(json: &str, options: &ParserOptions) -> Result<(T, Vec<Warning>)> {
+    let value = parse_with_options(json, options)?;
+    let _guard = DeserializeOptionsGuard::install(options);
+    let collector = WarningCollector::install();
+    let result = T::deserialize(value)?;
+    Ok((result, collector.take()))
+}
+
+/// A lossy or lenient conversion that [`from_str_checked`] accepted
+///
+/// Each warning carries the number as written in the input and the Rust type
+/// it was converted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A float type received a number it cannot hold exactly, so the value
+    /// was rounded. For example `16777217` into an `f32`, or a literal with
+    /// more significant digits than an `f64` keeps. Numbers like `0.1` are not
+    /// flagged, since they read back as the same text.
+    PrecisionLoss { value: String, ty: &'static str },
+    /// An integer type accepted a number written as a float, such as `3.0` or `1e3`
+    IntegerFromFloat { value: String, ty: &'static str },
+    /// A quoted number such as `"30"` was accepted under
+    /// [`ParserOptions::coerce_numbers_from_strings`]
+    NumberFromString { value: String, ty: &'static str },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::PrecisionLoss { value, ty } => write!(f, "{} was rounded to fit {}", value, ty),
+            Warning::IntegerFromFloat { value, ty } => write!(f, "float {} was converted to {}", value, ty),
+            Warning::NumberFromString { value, ty } => write!(f, "string {:?} was converted to {}", value, ty),
+        }
+    }
+}
+
+// Deserialize a JSON string on a best-effort basis, skipping malformed array
+// elements and returning their errors alongside the value
+pub fn from_str_lenient<T: Deserialize>(json: &str) -> Result<(T, Vec<Error>)> {
+    let options = ParserOptions { recover_array_elements: true, ..ParserOptions::default() };
+    let (value, errors) = parse_with_errors(json, &options)?;
+    let _guard = DeserializeOptionsGuard::install(&options);
+    Ok((T::deserialize(value)?, errors))
+}
+
+// Deserialize a JSON string with a seed that carries runtime context
+pub fn from_str_seed<T, S: DeserializeSeed<T>>(json: &str, seed: S) -> Result<T> {
+    let value = parse(json)?;
+    let _guard = DeserializeOptionsGuard::install(&ParserOptions::default());
+    seed.deserialize(value)
+}
+
+/// Match a JSON string against a fixed set of allowed values, returning the
+/// matching `&'static str` without allocating
+///
+/// Useful for enum-like string fields with a known vocabulary. Wrap it in a
+/// module to use with `#[fastjson(with = "...")]`:
+///
+/// ```
+/// use fastjson::{from_str, Deserialize};
+///
+/// mod level {
+///     use fastjson::{Result, Value};
+///     pub fn deserialize(value: Value) -> Result<&'static str> {
+///         fastjson::deserialize_static_str(value, &["debug", "info", "warn"])
+///     }
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     #[fastjson(with = "level")]
+///     level: &'static str,
+/// }
+///
+/// let event: Event = from_str(r#"{"level": "info"}"#).unwrap();
+/// assert_eq!(event.level, "info");
+/// assert!(from_str::<Event>(r#"{"level": "trace"}"#).is_err());
+/// ```
+pub fn deserialize_static_str(value: Value, allowed: &[&'static str]) -> Result<&'static str> {
+    match value {
+        Value::String(s) => allowed.iter().copied().find(|a| *a == s).ok_or_else(|| {
+            Error::TypeError(format!("unknown value {:?}, expected one of {:?}", s, allowed))
+        }),
+        _ => Err(Error::TypeError(format!("expected string, found {}", value.type_name()))),
+    }
+}
+
+// An array or object the parser has opened but not yet closed
+#[derive(Debug)]
+enum Frame {
+    Array(Vec<Value>),
+    // The map so far, and the key and position of the entry being parsed
+    Object(Map, String, usize),
+}
+
 // JSON parser
 struct Parser<'a> {
     input: &'a str,
+    options: &'a ParserOptions,
     chars: std::iter::Peekable<std::str::CharIndices<'a>>,
     pos: usize,
+    // Number of arrays and objects currently open
+    depth: usize,
+    // Whether the parser is inside a string, so recovery knows to skip its end
+    in_string: bool,
+    // Errors of array elements skipped under `recover_array_elements`
+    recovered: Vec<Error>,
+    // Buffers to fill instead of allocating, supplied by a ReusableParser
+    pool: BufferPool,
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
+    fn new(input: &'a str, options: &'a ParserOptions) -> Self {
+        // Skip a UTF-8 byte order mark at the very start, as written by some
+        // Windows tools. Positions still count from the start of the input.
+        let mut chars = input.char_indices().peekable();
+        chars.next_if(|&(_, c)| c == '\u{FEFF}');
         Self {
             input,
-            chars: input.char_indices().peekable(),
+            options,
+            chars,
             pos: 0,
+            depth: 0,
+            in_string: false,
+            recovered: Vec::new(),
+            pool: BufferPool::default(),
         }
     }
 
+    // Parse exactly one value, surrounded by nothing but whitespace
+    fn parse_document(&mut self) -> Result<(Value, Vec<Error>)> {
+        if let Some(max) = self.options.max_input_len {
+            if self.input.len() > max {
+                return Err(Error::LimitExceeded { limit: "max_input_len", position: max });
+            }
+        }
+        // Tell "nothing at all" apart from a document cut off part way
+        self.skip_whitespace();
+        if self.peek().is_none() {
+            return Err(Error::EmptyInput);
+        }
+        let value = self.parse()?;
+        
+        // Make sure we've consumed all input
+        self.skip_whitespace();
+        if let Some((pos, c)) = self.peek() {
+            return Err(Error::syntax(pos, format!("trailing character '{}' after JSON value", c)));
+        }
+        
+        Ok((value, std::mem::take(&mut self.recovered)))
+    }
+
     fn peek(&mut self) -> Option<(usize, char)> {
         self.chars.peek().cloned()
     }
 
+    // Byte position of the next unconsumed character
+    fn peek_pos(&mut self) -> usize {
+        match self.peek() {
+            Some((pos, _)) => pos,
+            None => self.input.len(),
+        }
+    }
+
     fn next(&mut self) -> Option<(usize, char)> {
         let next = self.chars.next();
         if let Some((pos, _)) = next {
@@ -297,7 +1063,8 @@ impl<'a> Parser<'a> {
 
     fn skip_whitespace(&mut self) {
         while let Some((_, ch)) = self.peek() {
-            if !ch.is_whitespace() {
+            // JSON whitespace is exactly these four, not Unicode whitespace
+            if !matches!(ch, ' ' | '\t' | '\n' | '\r') {
                 break;
             }
             self.next();
@@ -309,7 +1076,52 @@ impl<'a> Parser<'a> {
         self.parse_value()
     }
 
+    // Parse one value, keeping arrays and objects that are still open on an
+    // explicit stack rather than the call stack, so nesting depth is bounded
+    // only by `max_depth` and memory
     fn parse_value(&mut self) -> Result<Value> {
+        let mut stack = std::mem::take(&mut self.pool.frames);
+        let result = self.parse_frames(&mut stack);
+        stack.clear();
+        self.pool.frames = stack;
+        result
+    }
+
+    fn parse_frames(&mut self, stack: &mut Vec<Frame>) -> Result<Value> {
+        let base = self.depth;
+        loop {
+            let mut completed = match self.begin_value(stack) {
+                Ok(Some(value)) => Some(value),
+                // Opened a container; its first element or entry value follows
+                Ok(None) => continue,
+                Err(err) => {
+                    let level = stack.len();
+                    self.recover(stack, level, base, err)?;
+                    None
+                }
+            };
+
+            // Hand the value to the innermost open container, closing containers
+            // whose end follows, until another value has to be parsed
+            loop {
+                let level = stack.len();
+                if level == 0 {
+                    // Recovery always leaves the array it recovered in open
+                    return Ok(completed.unwrap_or_default());
+                }
+                match self.continue_container(stack, completed.take()) {
+                    Ok(Some(value)) => completed = Some(value),
+                    Ok(None) => break,
+                    // Errors in a container's own punctuation belong to its parent
+                    Err(err) => self.recover(stack, level - 1, base, err)?,
+                }
+            }
+        }
+    }
+
+    // Parse a scalar or an empty container, or open a container and push it
+    // onto `stack`, returning `None`
+    fn begin_value(&mut self, stack: &mut Vec<Frame>) -> Result<Option<Value>> {
         self.skip_whitespace();
         
         // Get the current character and position
@@ -319,35 +1131,139 @@ impl<'a> Parser<'a> {
         };
         
         // Dispatch to the appropriate parser based on the first character
-        match c {
-            'n' => self.parse_null(),
-            't' => self.parse_true(),
-            'f' => self.parse_false(),
-            '"' => self.parse_string(),
+        let value = match c {
+            'n' => self.parse_null()?,
+            't' => self.parse_true()?,
+            'f' => self.parse_false()?,
+            '"' => self.parse_string()?,
             '[' => {
-                // Special handling for array
-                let value = self.parse_array();
-                if value.is_err() {
-                    // Show detailed error message
-                    if let Err(err) = &value {
-                        Err(Error::syntax(pos, format!("Failed to parse array: {}", err)))
-                    } else {
-                        value
+                self.check_depth(pos)?;
+                self.next(); // Skip opening bracket
+                self.skip_whitespace();
+                let items = self.pool.array();
+                
+                // Check for empty array
+                if let Some((_, ']')) = self.peek() {
+                    self.next();
+                    return Ok(Some(Value::Array(items)));
+                }
+                self.depth += 1;
+                stack.push(Frame::Array(items));
+                return Ok(None);
+            }
+            '{' => {
+                self.check_depth(pos)?;
+                self.next(); // Skip opening brace
+                self.skip_whitespace();
+                let map = self.pool.object();
+                
+                // Check for empty object
+                if let Some((_, '}')) = self.peek() {
+                    self.next();
+                    return Ok(Some(Value::Object(map)));
+                }
+                self.depth += 1;
+                let (key, key_pos) = self.parse_key("'\"' or '}'")?;
+                stack.push(Frame::Object(map, key, key_pos));
+                return Ok(None);
+            }
+            '-' | '0'..='9' => self.parse_number()?,
+            _ => return Err(Error::syntax(pos, format!("unexpected character: {}", c))),
+        };
+        Ok(Some(value))
+    }
+    
+    // Add a finished value to the innermost container on `stack` (`None` for an
+    // array element skipped by recovery), then read the separator after it.
+    // Returns the container once its closing bracket is reached, or `None` when
+    // another element or entry value follows.
+    fn continue_container(&mut self, stack: &mut Vec<Frame>, completed: Option<Value>) -> Result<Option<Value>> {
+        self.skip_whitespace();
+        match stack.last_mut() {
+            Some(Frame::Array(items)) => {
+                items.extend(completed);
+                match self.peek() {
+                    Some((_, ',')) => {
+                        self.next();
+                        self.skip_whitespace();
+                        
+                        // JSON doesn't allow trailing commas, so this is an error
+                        if let Some((pos, ']')) = self.peek() {
+                            return Err(Error::syntax(pos, "trailing comma in array is not allowed in JSON"));
+                        }
+                        Ok(None)
+                    }
+                    Some((_, ']')) => {
+                        self.next();
+                        self.depth -= 1;
+                        match stack.pop() {
+                            Some(Frame::Array(items)) => Ok(Some(Value::Array(items))),
+                            _ => unreachable!(), // matched as an array above
+                        }
                     }
-                } else {
-                    value
+                    Some((pos, c)) => Err(Error::expected_found("',' or ']'", c, pos)),
+                    None => Err(Error::Eof),
                 }
-            },
-            '{' => self.parse_object(),
-            '-' | '0'..='9' => self.parse_number(),
-            _ => Err(Error::syntax(pos, format!("unexpected character: {}", c))),
+            }
+            Some(Frame::Object(map, key, key_pos)) => {
+                if let Some(value) = completed {
+                    self.insert_entry(map, std::mem::take(key), value, *key_pos)?;
+                }
+                match self.peek() {
+                    Some((_, ',')) => {
+                        self.next();
+                        self.skip_whitespace();
+                        
+                        // JSON doesn't allow trailing commas, so this is an error
+                        if let Some((pos, '}')) = self.peek() {
+                            return Err(Error::syntax(pos, "trailing comma in object is not allowed in JSON"));
+                        }
+                        
+                        (*key, *key_pos) = self.parse_key("'\"'")?;
+                        Ok(None)
+                    }
+                    Some((_, '}')) => {
+                        self.next();
+                        self.depth -= 1;
+                        match stack.pop() {
+                            Some(Frame::Object(map, ..)) => Ok(Some(Value::Object(map))),
+                            _ => unreachable!(), // matched as an object above
+                        }
+                    }
+                    Some((pos, c)) => Err(Error::expected_found("',' or '}'", c, pos)),
+                    None => Err(Error::Eof),
+                }
+            }
+            None => unreachable!(), // only called with a container open
+        }
+    }
+
+    // Under `recover_array_elements`, skip past the element that `err` occurred
+    // in, trying the innermost array among the first `limit` frames and then the
+    // arrays around it, and drop the containers opened inside that element.
+    // Gives `err` back when no array can recover.
+    fn recover(&mut self, stack: &mut Vec<Frame>, limit: usize, base: usize, mut err: Error) -> Result<()> {
+        if !self.options.recover_array_elements {
+            return Err(err);
+        }
+        for index in (0..limit).rev() {
+            if let Frame::Array(_) = stack[index] {
+                match self.skip_element(err, base + index + 1) {
+                    Ok(()) => {
+                        stack.truncate(index + 1);
+                        return Ok(());
+                    }
+                    Err(unrecovered) => err = unrecovered,
+                }
+            }
         }
+        Err(err)
     }
     
     // Split bool into two functions for clarity
     fn parse_true(&mut self) -> Result<Value> {
-        let pos = self.pos;
-        if self.pos + 4 <= self.input.len() && &self.input[self.pos..self.pos+4] == "true" {
+        let pos = self.peek_pos();
+        if self.input[pos..].starts_with("true") {
             for _ in 0..4 {
                 self.next();
             }
@@ -358,8 +1274,8 @@ impl<'a> Parser<'a> {
     }
     
     fn parse_false(&mut self) -> Result<Value> {
-        let pos = self.pos;
-        if self.pos + 5 <= self.input.len() && &self.input[self.pos..self.pos+5] == "false" {
+        let pos = self.peek_pos();
+        if self.input[pos..].starts_with("false") {
             for _ in 0..5 {
                 self.next();
             }
@@ -370,7 +1286,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_null(&mut self) -> Result<Value> {
-        let current_pos = self.pos;
+        let current_pos = self.peek_pos();
         
         if self.input[current_pos..].starts_with("null") {
             for _ in 0..4 {
@@ -384,7 +1300,7 @@ impl<'a> Parser<'a> {
 
     #[allow(dead_code)]
     fn parse_bool(&mut self) -> Result<Value> {
-        let current_pos = self.pos;
+        let current_pos = self.peek_pos();
         
         // Check for true
         if self.input[current_pos..].starts_with("true") {
@@ -408,8 +1324,9 @@ impl<'a> Parser<'a> {
 
     fn parse_string(&mut self) -> Result<Value> {
         self.next(); // Skip opening quote
+        self.in_string = true;
         
-        let mut result = String::new();
+        let mut result = self.pool.string();
         let mut escaped = false;
         
         loop {
@@ -444,18 +1361,25 @@ impl<'a> Parser<'a> {
                     result.push('\\');
                     escaped = false;
                 }
+                Some((_, '/')) if escaped => {
+                    result.push('/');
+                    escaped = false;
+                }
                 Some((_, 'u')) if escaped => {
                     // Parse unicode escape \uXXXX
-                    let mut code_point = 0;
-                    for _ in 0..4 {
-                        match self.next() {
-                            Some((_, c)) if c.is_ascii_hexdigit() => {
-                                code_point = code_point * 16 + c.to_digit(16).unwrap();
-                            }
-                            Some((p, c)) => {
-                                return Err(Error::syntax(p, format!("invalid unicode escape: {}", c)));
+                    let mut code_point = self.parse_hex4()?;
+
+                    // A high surrogate combines with a following \uXXXX low surrogate
+                    if (0xD800..0xDC00).contains(&code_point) {
+                        let mut ahead = self.chars.clone();
+                        if let (Some((_, '\\')), Some((_, 'u'))) = (ahead.next(), ahead.next()) {
+                            self.next();
+                            self.next();
+                            let low = self.parse_hex4()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(Error::syntax(self.pos, "invalid unicode code point"));
                             }
-                            None => return Err(Error::Eof),
+                            code_point = 0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00);
                         }
                     }
                     
@@ -476,46 +1400,55 @@ impl<'a> Parser<'a> {
             }
         }
         
+        self.in_string = false;
         Ok(Value::String(result))
     }
 
     fn parse_number(&mut self) -> Result<Value> {
-        let mut number_str = String::new();
-        let start_pos = self.pos;
+        let start_pos = self.peek_pos();
         
         // Check for negative sign
-        if let Some((_, '-')) = self.peek() {
-            number_str.push('-');
+        let negative = matches!(self.peek(), Some((_, '-')));
+        if negative {
             self.next();
         }
         
-        // Parse integer part
+        // Parse integer part, accumulating the digits directly while they fit in a u64
         let mut has_digits = false;
+        let mut mantissa: Option<u64> = Some(0);
         
-        // Handle leading zero
-        if let Some((_, '0')) = self.peek() {
-            number_str.push('0');
+        // Handle leading zero, which may not be followed by more digits
+        if let Some((zero_pos, '0')) = self.peek() {
             has_digits = true;
             self.next();
+            if matches!(self.peek(), Some((_, c)) if c.is_ascii_digit()) {
+                return Err(Error::syntax(zero_pos, "leading zeros are not allowed"));
+            }
         } else {
             // Handle other digits
             while let Some((_, c)) = self.peek() {
-                if !c.is_ascii_digit() {
-                    break;
-                }
-                number_str.push(c);
+                let digit = match c.to_digit(10) {
+                    Some(digit) => digit as u64,
+                    None => break,
+                };
+                mantissa = mantissa
+                    .and_then(|m| m.checked_mul(10))
+                    .and_then(|m| m.checked_add(digit));
                 has_digits = true;
                 self.next();
             }
         }
         
         if !has_digits {
-            return Err(Error::syntax(start_pos, "expected digit"));
+            let pos = self.peek_pos();
+            return Err(Error::syntax(pos, "expected digit"));
         }
         
+        let mut is_integer = true;
+        
         // Parse fractional part
         if let Some((_, '.')) = self.peek() {
-            number_str.push('.');
+            is_integer = false;
             self.next();
             
             let mut has_fractional_digits = false;
@@ -523,26 +1456,25 @@ impl<'a> Parser<'a> {
                 if !c.is_ascii_digit() {
                     break;
                 }
-                number_str.push(c);
                 has_fractional_digits = true;
                 self.next();
             }
             
             if !has_fractional_digits {
-                return Err(Error::syntax(self.pos, "expected digit after decimal point"));
+                let pos = self.peek_pos();
+                return Err(Error::syntax(pos, "expected digit after decimal point"));
             }
         }
         
         // Parse exponent
         if let Some((_, e)) = self.peek() {
             if e == 'e' || e == 'E' {
-                number_str.push(e);
+                is_integer = false;
                 self.next();
                 
                 // Check for exponent sign
                 if let Some((_, s)) = self.peek() {
                     if s == '+' || s == '-' {
-                        number_str.push(s);
                         self.next();
                     }
                 }
@@ -552,175 +1484,167 @@ impl<'a> Parser<'a> {
                     if !c.is_ascii_digit() {
                         break;
                     }
-                    number_str.push(c);
                     has_exponent_digits = true;
                     self.next();
                 }
                 
                 if !has_exponent_digits {
-                    return Err(Error::syntax(self.pos, "expected digit in exponent"));
+                    let pos = self.peek_pos();
+                    return Err(Error::syntax(pos, "expected digit in exponent"));
                 }
             }
         }
         
-        // Parse the number string
+        // A number runs up to the first character that can't continue it. Reject
+        // ones that look like a continuation, such as `42abc` or `1.2.3`, here so
+        // the error names the number in every context, not just at top level.
+        if let Some((pos, c)) = self.peek() {
+            if c.is_alphanumeric() || c == '.' || c == '_' {
+                let number = &self.input[start_pos..pos];
+                return Err(Error::syntax(pos, format!("unexpected character '{}' after number {}", c, number)));
+            }
+        }
+        
+        let number_str = &self.input[start_pos..self.peek_pos()];
+        if number_str.len() > self.options.max_number_length {
+            return Err(Error::LimitExceeded { limit: "max_number_length", position: start_pos });
+        }
+        
+        // Fast path: plain integers are kept exact. `-0` falls through so the
+        // float parser preserves its sign.
+        if let (true, Some(m)) = (is_integer, mantissa) {
+            if !negative {
+                return Ok(Value::Number(Number::from(m)));
+            }
+            if m != 0 && m <= i64::MAX as u64 + 1 {
+                return Ok(Value::Number(Number::from((m as i64).wrapping_neg())));
+            }
+        }
+        
+        // Fractions, exponents and overflowing integers go through the full
+        // float parser. Magnitudes beyond f64 would come back as infinity.
         match number_str.parse::<f64>() {
-            Ok(n) => Ok(Value::Number(n)),
+            Ok(n) if n.is_infinite() => Err(Error::syntax(start_pos, format!("number out of range: {}", number_str))),
+            Ok(n) => Ok(Value::Number(Number::from_parsed(n, number_str))),
             Err(_) => Err(Error::syntax(start_pos, format!("invalid number: {}", number_str))),
         }
     }
 
-    fn parse_array(&mut self) -> Result<Value> {
-        self.next(); // Skip opening bracket
-        self.skip_whitespace();
-        
-        let mut items = Vec::new();
-        
-        // Check for empty array
-        if let Some((_, ']')) = self.peek() {
-            self.next();
-            return Ok(Value::Array(items));
+    // Fail before opening another array or object past `max_depth`
+    fn check_depth(&self, position: usize) -> Result<()> {
+        if self.depth >= self.options.max_depth {
+            return Err(Error::LimitExceeded { limit: "max_depth", position });
+        }
+        Ok(())
+    }
+
+    // Recover from an element that failed to parse by skipping to the next ','
+    // or ']' of the array at `depth`, closing anything the element left open.
+    // Gives `err` back if the input ends or brackets don't match first.
+    fn skip_element(&mut self, err: Error, depth: usize) -> Result<()> {
+        let mut open = self.depth - depth;
+        self.depth = depth;
+        if self.in_string {
+            self.in_string = false;
+            if !self.skip_string_rest() {
+                return Err(err);
+            }
         }
         
-        // Parse first item
-        items.push(self.parse_value()?);
-        self.skip_whitespace();
-        
-        // Parse remaining items
         loop {
             match self.peek() {
-                Some((_, ',')) => {
+                None => return Err(err),
+                Some((_, '"')) => {
                     self.next();
-                    self.skip_whitespace();
-                    
-                    // JSON doesn't allow trailing commas, so this is an error
-                    if let Some((pos, ']')) = self.peek() {
-                        return Err(Error::syntax(pos, "trailing comma in array is not allowed in JSON"));
+                    if !self.skip_string_rest() {
+                        return Err(err);
                     }
-                    
-                    // Parse value after comma
-                    items.push(self.parse_value()?);
-                    self.skip_whitespace();
                 }
-                Some((_, ']')) => {
+                Some((_, '[' | '{')) => {
+                    open += 1;
                     self.next();
-                    break;
                 }
-                Some((pos, c)) => {
-                    return Err(Error::expected_found("',' or ']'", c, pos));
+                Some((_, ']' | '}')) if open > 0 => {
+                    open -= 1;
+                    self.next();
+                }
+                Some((_, ',' | ']')) if open == 0 => break,
+                Some((_, '}')) => return Err(err),
+                Some(_) => {
+                    self.next();
                 }
-                None => return Err(Error::Eof),
             }
         }
         
-        Ok(Value::Array(items))
+        self.recovered.push(err);
+        Ok(())
     }
 
-    fn parse_object(&mut self) -> Result<Value> {
-        self.next(); // Skip opening brace
-        self.skip_whitespace();
-        
-        let mut map = HashMap::new();
-        
-        // Check for empty object
-        if let Some((_, '}')) = self.peek() {
-            self.next();
-            return Ok(Value::Object(map));
+    // Consume the rest of a string up to its closing quote, returning false at end of input
+    fn skip_string_rest(&mut self) -> bool {
+        let mut escaped = false;
+        while let Some((_, c)) = self.next() {
+            match c {
+                '"' if !escaped => return true,
+                '\\' if !escaped => escaped = true,
+                _ => escaped = false,
+            }
         }
+        false
+    }
+
+    // Parse an object key and the ':' after it, skipping whitespace around
+    // both so the parser is left at the entry's value
+    fn parse_key(&mut self, expected: &'static str) -> Result<(String, usize)> {
+        self.skip_whitespace();
+        let key_pos = match self.peek() {
+            Some((pos, '"')) => pos,
+            Some((pos, c)) => return Err(Error::expected_found(expected, c, pos)),
+            None => return Err(Error::Eof),
+        };
+        let key = match self.parse_string()? {
+            Value::String(s) => s,
+            _ => unreachable!(), // parse_string always produces a string
+        };
         
-        // First key-value pair
-        if let Some((_, '"')) = self.peek() {
-            // Parse key as string
-            let key_value = self.parse_string()?;
-            let key = match key_value {
-                Value::String(s) => s,
-                _ => unreachable!(), // This should never happen since we just parsed a string
-            };
-            
-            // Expect colon
-            self.skip_whitespace();
-            match self.peek() {
-                Some((_, ':')) => {
-                    self.next();
-                }
-                Some((pos, c)) => {
-                    return Err(Error::expected_found("':'", c, pos));
-                }
-                None => return Err(Error::Eof),
+        // Expect colon
+        self.skip_whitespace();
+        match self.peek() {
+            Some((_, ':')) => {
+                self.next();
             }
-            
-            // Parse value (skip whitespace before value)
-            self.skip_whitespace();
-            let value = self.parse_value()?;
-            
-            // Insert key-value pair
-            map.insert(key, value);
-            self.skip_whitespace();
-        } else if let Some((pos, c)) = self.peek() {
-            return Err(Error::expected_found("'\"' or '}'", c, pos));
-        } else {
-            return Err(Error::Eof);
+            Some((pos, c)) => {
+                return Err(Error::expected_found("':'", c, pos));
+            }
+            None => return Err(Error::Eof),
         }
-        
-        // Remaining key-value pairs
-        loop {
-            match self.peek() {
-                Some((_, ',')) => {
-                    self.next();
-                    self.skip_whitespace();
-                    
-                    // JSON doesn't allow trailing commas, so this is an error
-                    if let Some((pos, '}')) = self.peek() {
-                        return Err(Error::syntax(pos, "trailing comma in object is not allowed in JSON"));
-                    }
-                    
-                    // println!("Position after comma: {}", self.pos);
-                    
-                    // Parse key
-                    if let Some((_, '"')) = self.peek() {
-                        // Parse key as string
-                        let key_value = self.parse_string()?;
-                        let key = match key_value {
-                            Value::String(s) => s,
-                            _ => unreachable!(), // This should never happen since we just parsed a string
-                        };
-                        
-                        // Expect colon
-                        self.skip_whitespace();
-                        match self.peek() {
-                            Some((_, ':')) => {
-                                self.next();
-                            }
-                            Some((pos, c)) => {
-                                return Err(Error::expected_found("':'", c, pos));
-                            }
-                            None => return Err(Error::Eof),
-                        }
-                        
-                        // Parse value (skip whitespace before value)
-                        self.skip_whitespace();
-                        let value = self.parse_value()?;
-                        
-                        // Insert key-value pair
-                        map.insert(key, value);
-                        self.skip_whitespace();
-                    } else if let Some((pos, c)) = self.peek() {
-                        return Err(Error::expected_found("'\"'", c, pos));
-                    } else {
-                        return Err(Error::Eof);
-                    }
-                }
-                Some((_, '}')) => {
-                    self.next();
-                    break;
+        self.skip_whitespace();
+        Ok((key, key_pos))
+    }
+
+    // Read the four hex digits of a \uXXXX escape
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let mut code_point = 0;
+        for _ in 0..4 {
+            match self.next() {
+                Some((_, c)) if c.is_ascii_hexdigit() => {
+                    code_point = code_point * 16 + c.to_digit(16).unwrap();
                 }
-                Some((pos, c)) => {
-                    return Err(Error::expected_found("',' or '}'", c, pos));
+                Some((p, c)) => {
+                    return Err(Error::syntax(p, format!("invalid unicode escape: {}", c)));
                 }
                 None => return Err(Error::Eof),
             }
         }
-        
-        Ok(Value::Object(map))
+        Ok(code_point)
+    }
+
+    // Add a parsed entry to an object, enforcing the duplicate key option
+    fn insert_entry(&self, map: &mut Map, key: String, value: Value, pos: usize) -> Result<()> {
+        if self.options.deny_duplicate_keys && map.contains_key(&key) {
+            return Err(Error::syntax(pos, format!("duplicate key \"{}\" in object", key)));
+        }
+        map.insert(key, value);
+        Ok(())
     }
 }
\ No newline at end of file
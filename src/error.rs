@@ -9,10 +9,15 @@ pub enum Error {
     /// Unexpected end of input
     Eof,
 
+    /// The input has no JSON value at all: it is empty or only whitespace
+    EmptyInput,
+
     /// Invalid syntax error at specific position
     Syntax {
         position: usize,
         message: String,
+        /// Input surrounding the error position, with a caret marker
+        snippet: Option<String>,
     },
 
     /// Expected a certain token but found something else
@@ -20,6 +25,17 @@ pub enum Error {
         expected: &'static str,
         found: String,
         position: usize,
+        /// Input surrounding the error position, with a caret marker
+        snippet: Option<String>,
+    },
+
+    /// The input is well-formed so far but exceeds a configured resource limit,
+    /// such as nesting depth or number length
+    ///
+    /// `limit` names the `ParserOptions` field that was exceeded.
+    LimitExceeded {
+        limit: &'static str,
+        position: usize,
     },
 
     /// Missing required field
@@ -40,11 +56,17 @@ impl fmt::Display for Error {
         match self {
             Error::Io(msg) => write!(f, "I/O error: {}", msg),
             Error::Eof => write!(f, "Unexpected end of input"),
-            Error::Syntax { position, message } => {
-                write!(f, "Invalid syntax at position {}: {}", position, message)
+            Error::EmptyInput => write!(f, "Empty JSON input"),
+            Error::Syntax { position, message, snippet } => {
+                write!(f, "Invalid syntax at position {}: {}", position, message)?;
+                write_snippet(f, snippet)
+            }
+            Error::ExpectedFound { expected, found, position, snippet } => {
+                write!(f, "Expected {} but found {} at position {}", expected, found, position)?;
+                write_snippet(f, snippet)
             }
-            Error::ExpectedFound { expected, found, position } => {
-                write!(f, "Expected {} but found {} at position {}", expected, found, position)
+            Error::LimitExceeded { limit, position } => {
+                write!(f, "Limit {} exceeded at position {}", limit, position)
             }
             Error::MissingField(field) => write!(f, "Missing field: {}", field),
             Error::UnknownField(field) => write!(f, "Unknown field: {}", field),
@@ -54,6 +76,13 @@ impl fmt::Display for Error {
     }
 }
 
+fn write_snippet(f: &mut fmt::Formatter<'_>, snippet: &Option<String>) -> fmt::Result {
+    match snippet {
+        Some(snippet) => write!(f, "\n{}", snippet),
+        None => Ok(()),
+    }
+}
+
 impl std::error::Error for Error {}
 
 /// Result type for serialization and deserialization operations
@@ -68,6 +97,7 @@ impl Error {
         Error::Syntax {
             position,
             message: msg.to_string(),
+            snippet: None,
         }
     }
 
@@ -76,6 +106,41 @@ impl Error {
             expected,
             found: found.to_string(),
             position,
+            snippet: None,
         }
     }
+
+    /// Attach the input surrounding the error position to positioned errors
+    pub(crate) fn with_snippet(mut self, input: &str) -> Self {
+        match &mut self {
+            Error::Syntax { position, snippet, .. } | Error::ExpectedFound { position, snippet, .. }
+                if snippet.is_none() =>
+            {
+                *snippet = Some(make_snippet(input, *position));
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Number of characters shown on either side of an error position
+const SNIPPET_CONTEXT: usize = 10;
+
+// Render the input around `position` on one line, with a caret under the error
+fn make_snippet(input: &str, position: usize) -> String {
+    let mut position = position.min(input.len());
+    while !input.is_char_boundary(position) {
+        position -= 1;
+    }
+
+    let before: Vec<char> = input[..position].chars().rev().take(SNIPPET_CONTEXT).collect();
+    let after = input[position..].chars().take(SNIPPET_CONTEXT);
+
+    // Keep the snippet on a single line so the caret lines up
+    let printable = |c: char| if c.is_control() { ' ' } else { c };
+    let mut line: String = before.iter().rev().map(|&c| printable(c)).collect();
+    line.extend(after.map(printable));
+
+    format!("{}\n{}^", line, " ".repeat(before.len()))
 }
\ No newline at end of file
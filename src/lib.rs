@@ -43,13 +43,21 @@
 
 mod error;
 mod value;
+mod number;
+pub mod map;
 mod ser;
 mod de;
+pub mod base64;
+pub mod columnar;
+#[cfg(feature = "serde_json")]
+mod serde_json_compat;
 
 pub use error::{Error, Result};
 pub use value::Value;
-pub use ser::{Serialize, to_string, to_string_pretty};
-pub use de::{Deserialize, from_str, parse};
+pub use map::Map;
+pub use number::Number;
+pub use ser::{InfallibleSerialize, JsonSerializer, Serialize, SerializeOptions, Serializer, serialize_iter, to_string, to_string_infallible, to_string_pretty, to_string_with, to_vec, to_vec_pretty};
+pub use de::{Deserialize, DeserializeSeed, IgnoredAny, deserialize_static_str, from_str, from_str_checked, from_str_lenient, from_str_many, from_str_seed, from_str_with_options, parse, parse_many, parse_with_errors, parse_with_options, ParserOptions, ReusableParser, Warning};
 
 // Re-export derive macros
 pub use fastjson_derive::{Serialize, Deserialize};
@@ -64,7 +72,7 @@ mod tests {
         assert_eq!(to_string(&true).unwrap(), "true");
         assert_eq!(to_string(&false).unwrap(), "false");
         assert_eq!(to_string(&42_i32).unwrap(), "42");
-        assert_eq!(to_string(&3.14_f64).unwrap(), "3.14");
+        assert_eq!(to_string(&3.25_f64).unwrap(), "3.25");
         assert_eq!(to_string("hello").unwrap(), "\"hello\"");
         assert_eq!(to_string("hello\nworld").unwrap(), "\"hello\\nworld\"");
     }
@@ -103,7 +111,7 @@ mod tests {
 
     #[test]
     fn test_deserialize_primitive_types() {
-        assert_eq!(from_str::<bool>("true").unwrap(), true);
+        assert!(from_str::<bool>("true").unwrap());
         // Skip integer and float tests temporarily
         
         assert_eq!(from_str::<String>("\"hello\"").unwrap(), "hello".to_string());
@@ -120,7 +128,7 @@ mod tests {
         let json = "{\"name\": \"Alice\", \"age\": 30}";
         let mut expected = HashMap::new();
         expected.insert("name".to_string(), Value::String("Alice".to_string()));
-        expected.insert("age".to_string(), Value::Number(30.0));
+        expected.insert("age".to_string(), Value::from(30.0));
         let parsed: HashMap<String, Value> = from_str(json).unwrap();
         assert_eq!(parsed, expected);
     }
@@ -130,14 +138,14 @@ mod tests {
         // Test a simple number with whitespace
         let json = " 42 ";
         let parsed = parse(json).unwrap();
-        assert_eq!(parsed, Value::Number(42.0));
+        assert_eq!(parsed, Value::from(42.0));
         
         // Test a simple object with whitespace
         let json = " { \"age\" : 30 } ";
         let parsed = parse(json).unwrap();
         
         if let Value::Object(map) = parsed {
-            assert_eq!(map.get("age"), Some(&Value::Number(30.0)));
+            assert_eq!(map.get("age"), Some(&Value::from(30.0)));
         } else {
             panic!("Expected object");
         }
@@ -148,7 +156,7 @@ mod tests {
         
         if let Value::Object(map) = parsed {
             assert_eq!(map.get("name"), Some(&Value::String("Alice".to_string())));
-            assert_eq!(map.get("age"), Some(&Value::Number(30.0)));
+            assert_eq!(map.get("age"), Some(&Value::from(30.0)));
         } else {
             panic!("Expected object");
         }
@@ -161,4 +169,1441 @@ mod tests {
         assert!(parse("\"unterminated").is_err());
         assert!(parse("invalid").is_err());
     }
+
+    #[test]
+    fn test_value_approx_eq() {
+        let sum = Value::from(0.1 + 0.2);
+        let expected = Value::from(0.3);
+        assert_ne!(sum, expected);
+        assert!(sum.approx_eq(&expected, 1e-9));
+        assert!(!sum.approx_eq(&Value::from(0.31), 1e-9));
+
+        // Recurses through arrays and objects, ignoring object key order
+        let parsed = parse("{\"a\": [0.30000000000000004, 1], \"b\": {\"c\": 2}}").unwrap();
+        let expected = parse("{\"b\": {\"c\": 2}, \"a\": [0.3, 1]}").unwrap();
+        assert!(parsed.approx_eq(&expected, 1e-9));
+
+        // Structure must still match exactly
+        let shorter = parse("{\"a\": [0.3], \"b\": {\"c\": 2}}").unwrap();
+        assert!(!parsed.approx_eq(&shorter, 1e-9));
+        assert!(!Value::from(0.0).approx_eq(&Value::Bool(false), 1.0));
+    }
+
+    #[test]
+    fn test_duration_round_trip() {
+        use std::time::Duration;
+
+        let timeout = Duration::new(30, 500_000_000);
+        let json = to_string(&timeout).unwrap();
+        assert_eq!(json, "{\"secs\": 30, \"nanos\": 500000000}");
+        assert_eq!(from_str::<Duration>(&json).unwrap(), timeout);
+
+        assert!(from_str::<Duration>("{\"secs\": 1, \"nanos\": 1000000000}").is_err());
+        assert!(from_str::<Duration>("{\"secs\": 1}").is_err());
+        assert!(from_str::<Duration>("30").is_err());
+    }
+
+    #[test]
+    fn test_error_snippet() {
+        let err = parse("{\"name\": \"Alice\", \"age\": x}").unwrap_err();
+        match &err {
+            Error::Syntax { position, snippet, .. } => {
+                assert_eq!(*position, 25);
+                assert_eq!(snippet.as_deref(), Some("\", \"age\": x}\n          ^"));
+            }
+            other => panic!("expected syntax error, found {:?}", other),
+        }
+        assert_eq!(
+            err.to_string(),
+            "Invalid syntax at position 25: unexpected character: x\n\", \"age\": x}\n          ^"
+        );
+
+        // Newlines in the context are flattened so the caret stays aligned
+        let err = parse("{\n  \"a\" 1\n}").unwrap_err();
+        match err {
+            Error::ExpectedFound { snippet: Some(snippet), .. } => {
+                assert_eq!(snippet, "{   \"a\" 1 }\n        ^");
+            }
+            other => panic!("expected ExpectedFound error, found {:?}", other),
+        }
+
+        // Errors without a position carry no snippet
+        assert_eq!(parse("\"abc").unwrap_err().to_string(), "Unexpected end of input");
+    }
+
+    #[test]
+    fn test_canonical_string() {
+        let a = parse("{\"b\": [1, {\"z\": true, \"a\": null}], \"a\": \"x\", \"c\": {}}").unwrap();
+        let b = parse(" {\"c\":{},\"a\":\"x\",\"b\":[1,{\"a\":null,\"z\":true}]} ").unwrap();
+
+        let canonical = a.to_canonical_string();
+        assert_eq!(canonical, "{\"a\":\"x\",\"b\":[1,{\"a\":null,\"z\":true}],\"c\":{}}");
+        assert_eq!(canonical, b.to_canonical_string());
+        assert_eq!(parse(&canonical).unwrap(), a);
+
+        // Control characters are always escaped
+        let text = Value::String("tab\there\u{1}".to_string());
+        assert_eq!(text.to_canonical_string(), "\"tab\\there\\u0001\"");
+    }
+
+    #[test]
+    fn test_value_len() {
+        assert_eq!(parse("[1, 2, 3]").unwrap().len(), Some(3));
+        assert_eq!(parse("{\"a\": 1}").unwrap().len(), Some(1));
+        assert_eq!(Value::String("héllo".to_string()).len(), Some(6));
+        assert_eq!(Value::Null.len(), None);
+        assert_eq!(Value::Bool(true).len(), None);
+        assert_eq!(Value::from(0.0).len(), None);
+
+        assert!(parse("[]").unwrap().is_empty());
+        assert!(parse("{}").unwrap().is_empty());
+        assert!(Value::String(String::new()).is_empty());
+        assert!(!parse("[null]").unwrap().is_empty());
+        assert!(!Value::Null.is_empty());
+        assert!(!Value::from(0.0).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        // Spec-permissive by default: the last value wins
+        let value = parse("{\"a\":1,\"a\":2}").unwrap();
+        assert_eq!(value.get("a"), Some(&Value::from(2.0)));
+
+        let strict = ParserOptions {
+            deny_duplicate_keys: true,
+            ..ParserOptions::default()
+        };
+        match parse_with_options("{\"a\":1,\"a\":2}", &strict) {
+            Err(Error::Syntax { position, message, .. }) => {
+                assert_eq!(position, 7);
+                assert_eq!(message, "duplicate key \"a\" in object");
+            }
+            other => panic!("expected duplicate key error, found {:?}", other),
+        }
+
+        // Nested objects are checked too, and distinct keys are fine
+        assert!(parse_with_options("{\"a\": {\"b\": 1, \"b\": 1}}", &strict).is_err());
+        assert!(parse_with_options("{\"a\": {\"a\": 1}, \"b\": 2}", &strict).is_ok());
+        assert!(from_str_with_options::<HashMap<String, i32>>("{\"a\":1,\"a\":2}", &strict).is_err());
+    }
+
+    #[test]
+    fn test_tuples() {
+        let pairs: &[(&str, i32)] = &[("a", 1), ("b", 2)];
+        assert_eq!(to_string(pairs).unwrap(), "[[\"a\", 1], [\"b\", 2]]");
+        assert_eq!(to_string(&pairs).unwrap(), "[[\"a\", 1], [\"b\", 2]]");
+
+        let entries = vec![
+            ("x".to_string(), Value::Bool(true)),
+            ("y".to_string(), Value::Null),
+        ];
+        let json = to_string(&entries).unwrap();
+        assert_eq!(json, "[[\"x\", true], [\"y\", null]]");
+        assert_eq!(from_str::<Vec<(String, Value)>>(&json).unwrap(), entries);
+
+        assert_eq!(from_str::<(u8, String, bool)>("[1, \"two\", false]").unwrap(), (1, "two".to_string(), false));
+        assert!(from_str::<(u8, u8)>("[1]").is_err());
+        assert!(from_str::<(u8, u8)>("[1, 2, 3]").is_err());
+        assert!(from_str::<(u8,)>("{}").is_err());
+    }
+
+    #[test]
+    fn test_from_str_seed() {
+        use std::marker::PhantomData;
+
+        let nums: Vec<i32> = from_str_seed("[1, 2, 3]", PhantomData).unwrap();
+        assert_eq!(nums, vec![1, 2, 3]);
+
+        // A seed borrowing external state
+        struct Scaled<'a>(&'a f64);
+        impl DeserializeSeed<f64> for Scaled<'_> {
+            fn deserialize(self, value: Value) -> Result<f64> {
+                Ok(f64::deserialize(value)? * self.0)
+            }
+        }
+        let factor = 2.5;
+        assert_eq!(from_str_seed("4", Scaled(&factor)).unwrap(), 10.0);
+        assert!(from_str_seed("[1", Scaled(&factor)).is_err());
+    }
+
+    #[test]
+    fn test_output_paths_agree() {
+        let mut obj = Map::new();
+        obj.insert("ctl\u{1}\"key".to_string(), Value::String("a\u{7}b\n\\".to_string()));
+        let values = vec![
+            Value::Null,
+            Value::from(-0.5),
+            Value::from(42.0),
+            Value::String("tab\there \u{1f}".to_string()),
+            Value::Array(vec![Value::Bool(true), Value::from(1e-7)]),
+            Value::Object(obj),
+        ];
+        for value in &values {
+            let compact = to_string(value).unwrap();
+            assert_eq!(value.to_string(), compact);
+            assert_eq!(parse(&compact).unwrap(), *value);
+            assert_eq!(parse(&to_string_pretty(value).unwrap()).unwrap(), *value);
+        }
+
+        // Control characters and keys are escaped identically when pretty printing
+        let pretty = to_string_pretty(&values[5]).unwrap();
+        assert_eq!(pretty, "{\n  \"ctl\\u0001\\\"key\": \"a\\u0007b\\n\\\\\"\n}");
+    }
+
+    #[test]
+    fn test_float_round_trip() {
+        assert_eq!(to_string(&1e20).unwrap(), "1e20");
+        assert_eq!(to_string(&1.5e-7).unwrap(), "1.5e-7");
+        assert_eq!(to_string(&0.1).unwrap(), "0.1");
+        assert_eq!(to_string(&2.0).unwrap(), "2.0");
+        assert_eq!(to_string(&-0.0).unwrap(), "-0.0");
+        assert_eq!(to_string(&9007199254740993.0).unwrap(), "9007199254740992.0");
+
+        // Integral floats read back as floats, not integers
+        let back = parse(&to_string(&Value::from(2.0)).unwrap()).unwrap();
+        assert!(back.as_number().unwrap().is_f64());
+        assert_eq!(back.as_i64(), None);
+
+        let check = |n: f64| {
+            let json = to_string(&n).unwrap();
+            let back: f64 = from_str(&json).unwrap();
+            assert_eq!(back.to_bits(), n.to_bits(), "{} printed as {}", n, json);
+        };
+
+        let specials = [
+            0.0, -0.0, 1.0, -1.0, 0.1, 0.2, 0.3, 1.0 / 3.0, 2.0 / 3.0,
+            f64::EPSILON, f64::MIN_POSITIVE, f64::MAX, f64::MIN,
+            5e-324, -5e-324, 2.225073858507201e-308, 1e-310,
+            123456789.12345679, 1e15, 1e16, 1e17, 1e21, 1e-5, 1e-6, 1e-7,
+        ];
+        for n in specials {
+            check(n);
+        }
+
+        // Sweep bit patterns across every exponent, including subnormals
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..20_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let n = f64::from_bits(state);
+            if n.is_finite() {
+                check(n);
+            }
+            check(f64::from_bits(state & 0x800F_FFFF_FFFF_FFFF));
+        }
+    }
+
+    #[test]
+    fn test_path_round_trip() {
+        use std::path::{Path, PathBuf};
+
+        let path = PathBuf::from("config/app.json");
+        let json = to_string(&path).unwrap();
+        assert_eq!(json, "\"config/app.json\"");
+        assert_eq!(from_str::<PathBuf>(&json).unwrap(), path);
+        assert_eq!(to_string(Path::new("./data")).unwrap(), "\"./data\"");
+        assert!(from_str::<PathBuf>("42").is_err());
+
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+            let invalid = Path::new(OsStr::from_bytes(b"bad\xffname"));
+            assert!(to_string(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_net_addresses() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+        fn round_trip<T: Serialize + Deserialize + PartialEq + std::fmt::Debug>(value: T, expected: &str) {
+            let json = to_string(&value).unwrap();
+            assert_eq!(json, expected);
+            assert_eq!(from_str::<T>(&json).unwrap(), value);
+        }
+
+        let v4 = Ipv4Addr::new(192, 168, 0, 1);
+        let v6 = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        round_trip(v4, "\"192.168.0.1\"");
+        round_trip(v6, "\"fe80::1\"");
+        round_trip(IpAddr::V4(v4), "\"192.168.0.1\"");
+        round_trip(IpAddr::V6(v6), "\"fe80::1\"");
+        round_trip(SocketAddrV4::new(v4, 8080), "\"192.168.0.1:8080\"");
+        round_trip(SocketAddrV6::new(v6, 443, 0, 0), "\"[fe80::1]:443\"");
+        round_trip(SocketAddr::from((v4, 80)), "\"192.168.0.1:80\"");
+        round_trip(SocketAddr::from((v6, 80)), "\"[fe80::1]:80\"");
+
+        match from_str::<Ipv4Addr>("\"999.0.0.1\"") {
+            Err(Error::TypeError(msg)) => assert!(msg.contains("invalid Ipv4Addr")),
+            other => panic!("expected type error, got {:?}", other),
+        }
+        assert!(from_str::<SocketAddr>("\"127.0.0.1\"").is_err());
+        assert!(from_str::<IpAddr>("[127, 0, 0, 1]").is_err());
+    }
+
+    #[test]
+    fn test_try_from_value() {
+        let value = parse(r#"{"name": "fastjson", "stars": 42, "ratio": 0.5, "ok": true, "tags": ["a"]}"#).unwrap();
+
+        assert_eq!(String::try_from(value.get("name").unwrap()).unwrap(), "fastjson");
+        assert_eq!(i64::try_from(value.get("stars").unwrap()).unwrap(), 42);
+        assert_eq!(f64::try_from(value.get("ratio").unwrap()).unwrap(), 0.5);
+        assert!(bool::try_from(value.get("ok").unwrap()).unwrap());
+        assert_eq!(Vec::<Value>::try_from(value.get("tags").unwrap()).unwrap(), vec![Value::String("a".to_string())]);
+
+        let map = HashMap::<String, Value>::try_from(value.clone()).unwrap();
+        assert_eq!(map.len(), 5);
+        let owned: String = value.get("name").unwrap().clone().try_into().unwrap();
+        assert_eq!(owned, "fastjson");
+
+        assert!(matches!(bool::try_from(value.get("name").unwrap()), Err(Error::TypeError(_))));
+        assert!(matches!(i64::try_from(value.get("ratio").unwrap()), Err(Error::TypeError(_))));
+        assert!(matches!(String::try_from(Value::Null), Err(Error::TypeError(_))));
+        assert!(matches!(f64::try_from(value.get("tags").unwrap()), Err(Error::TypeError(_))));
+        assert!(matches!(Vec::<Value>::try_from(&value), Err(Error::TypeError(_))));
+        assert!(matches!(HashMap::<String, Value>::try_from(value.get("tags").unwrap()), Err(Error::TypeError(_))));
+
+        // A borrowed mismatch reports the same error as an owned one
+        let large = Value::Array((0..1000).map(Value::from).collect());
+        assert_eq!(bool::try_from(&large).unwrap_err(), bool::try_from(large.clone()).unwrap_err());
+        assert_eq!(Map::try_from(&value).unwrap().len(), value.as_object().unwrap().len());
+    }
+
+    #[test]
+    fn test_value_builders() {
+        let mut obj = Value::Null;
+        assert_eq!(obj.insert("name", "fastjson").unwrap(), None);
+        assert_eq!(obj.insert("stars", 42).unwrap(), None);
+        assert_eq!(obj.insert("name", String::from("renamed")).unwrap(), Some(Value::String("fastjson".to_string())));
+
+        let mut tags = Value::default();
+        tags.push("fast").unwrap();
+        tags.push(true).unwrap();
+        tags.push(1.5).unwrap();
+        obj.insert("tags", tags).unwrap();
+
+        assert_eq!(
+            obj.to_canonical_string(),
+            r#"{"name":"renamed","stars":42,"tags":["fast",true,1.5]}"#
+        );
+
+        // Incompatible variants are left unchanged
+        let mut number = Value::from(1.0);
+        assert!(number.insert("a", 1).is_err());
+        assert!(number.push(1).is_err());
+        assert_eq!(number, Value::from(1.0));
+        assert!(obj.push(1).is_err());
+    }
+
+    #[test]
+    fn test_coerce_numbers_from_strings() {
+        let lenient = ParserOptions { coerce_numbers_from_strings: true, ..ParserOptions::default() };
+
+        assert_eq!(from_str::<f64>("5").unwrap(), 5.0);
+        assert!(from_str::<f64>("true").is_err());
+        assert!(from_str::<f64>("\"5.0\"").is_err());
+
+        assert_eq!(from_str_with_options::<f64>("\"5.0\"", &lenient).unwrap(), 5.0);
+        assert_eq!(from_str_with_options::<f32>("\"-1.5e2\"", &lenient).unwrap(), -150.0);
+        assert_eq!(from_str_with_options::<Vec<f64>>("[\"1\", 2]", &lenient).unwrap(), vec![1.0, 2.0]);
+        assert!(from_str_with_options::<f64>("\"five\"", &lenient).is_err());
+        assert!(from_str_with_options::<f64>("\"NaN\"", &lenient).is_err());
+        assert!(from_str_with_options::<f64>("true", &lenient).is_err());
+
+        // Integers accept plain integer strings, range checked like numbers
+        assert_eq!(from_str_with_options::<u32>("\"30\"", &lenient).unwrap(), 30);
+        assert_eq!(from_str_with_options::<i64>("\"-9223372036854775808\"", &lenient).unwrap(), i64::MIN);
+        assert_eq!(from_str_with_options::<u64>("\"18446744073709551615\"", &lenient).unwrap(), u64::MAX);
+        assert!(from_str_with_options::<u32>("\"abc\"", &lenient).is_err());
+        assert!(from_str_with_options::<u32>("\"5.0\"", &lenient).is_err());
+        assert!(from_str_with_options::<u8>("\"256\"", &lenient).is_err());
+        assert!(from_str_with_options::<u32>("\"-1\"", &lenient).is_err());
+
+        // The option does not leak past the call
+        assert!(from_str::<f64>("\"5.0\"").is_err());
+        assert!(from_str::<u32>("\"30\"").is_err());
+    }
+
+    #[test]
+    fn test_integer_parsing() {
+        for text in [
+            "0", "-0", "7", "-42", "9007199254740993", "-9007199254740993",
+            "18446744073709551615", "18446744073709551616", "123456789012345678901234567890",
+        ] {
+            let expected: f64 = text.parse().unwrap();
+            match parse(text).unwrap() {
+                Value::Number(n) => assert_eq!(n.as_f64().to_bits(), expected.to_bits(), "{}", text),
+                other => panic!("expected number for {}, got {:?}", text, other),
+            }
+        }
+        assert_eq!(parse("[1,-2,3.5,4e2]").unwrap(), Value::Array(vec![
+            Value::from(1.0), Value::from(-2.0), Value::from(3.5), Value::from(400.0),
+        ]));
+        assert!(parse("-").is_err());
+        assert!(parse("01").is_err());
+        assert!(parse("1.").is_err());
+    }
+
+    #[test]
+    fn test_json_serializer() {
+        fn via_serializer<T: Serialize + ?Sized>(value: &T) -> String {
+            let mut serializer = JsonSerializer::new();
+            value.serialize_to(&mut serializer).unwrap();
+            serializer.into_string()
+        }
+
+        assert_eq!(via_serializer(&vec![Some(1), None]), "[1, null]");
+        assert_eq!(via_serializer("a\"b"), "\"a\\\"b\"");
+        assert_eq!(via_serializer(&Vec::<Vec<i32>>::from([vec![], vec![1, 2]])), "[[], [1, 2]]");
+        assert_eq!(via_serializer(&(true, 2.5, "x")), "[true, 2.5, \"x\"]");
+
+        let value = parse(r#"{"a": [1, {"b": null}], "c": {}, "d": "e"}"#).unwrap();
+        assert_eq!(via_serializer(&value), value.to_string());
+
+        let mut map = HashMap::new();
+        map.insert("k1", vec![1]);
+        map.insert("k2", vec![]);
+        assert_eq!(parse(&via_serializer(&map)).unwrap(), parse(&to_string(&map).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_128_bit_integers() {
+        let json = to_string(&u128::MAX).unwrap();
+        assert_eq!(json, "\"340282366920938463463374607431768211455\"");
+        assert_eq!(from_str::<u128>(&json).unwrap(), u128::MAX);
+
+        let json = to_string(&vec![i128::MIN, -1, 0]).unwrap();
+        assert_eq!(json, "[\"-170141183460469231731687303715884105728\", \"-1\", \"0\"]");
+        assert_eq!(from_str::<Vec<i128>>(&json).unwrap(), vec![i128::MIN, -1, 0]);
+
+        // Overflow, sign and non-string input are type errors
+        assert!(matches!(from_str::<u128>("\"340282366920938463463374607431768211456\""), Err(Error::TypeError(_))));
+        assert!(matches!(from_str::<u128>("\"-1\""), Err(Error::TypeError(_))));
+        assert!(matches!(from_str::<i128>("5"), Err(Error::TypeError(_))));
+        assert!(matches!(from_str::<i128>("\"1.5\""), Err(Error::TypeError(_))));
+    }
+
+    #[test]
+    fn test_base64() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (&[0, 255, 254, 1, 128], "AP/+AYA="),
+        ];
+        for (bytes, text) in cases {
+            assert_eq!(base64::encode(bytes), text);
+            assert_eq!(base64::decode(text).unwrap(), bytes);
+        }
+
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64::decode(&base64::encode(&all)).unwrap(), all);
+
+        for bad in ["Zg=", "Zg===", "Z===", "Zg==Zm9v", "Zm9v!A==", "Zh==", "Zm9=", "=Zm9"] {
+            assert!(matches!(base64::decode(bad), Err(Error::Custom(_))), "{}", bad);
+        }
+        assert!(matches!(base64::deserialize::<Vec<u8>>(Value::Null), Err(Error::TypeError(_))));
+    }
+
+    #[test]
+    fn test_value_remove() {
+        let mut value = parse(r#"{"user": {"name": "a", "password": "secret", "emails": ["x", "y"]}, "a/b": 1, "m~n": 2}"#).unwrap();
+
+        assert_eq!(value.pointer_remove("/user/password"), Some(Value::String("secret".to_string())));
+        assert_eq!(value.pointer_remove("/user/password"), None);
+        assert_eq!(value.pointer_remove("/user/emails/0"), Some(Value::String("x".to_string())));
+        assert_eq!(value.pointer_remove("/a~1b"), Some(Value::from(1.0)));
+        assert_eq!(value.pointer_remove("/m~0n"), Some(Value::from(2.0)));
+        assert_eq!(value.to_canonical_string(), r#"{"user":{"emails":["y"],"name":"a"}}"#);
+
+        // Missing, malformed or non-container paths
+        assert_eq!(value.pointer_remove("/user/emails/5"), None);
+        assert_eq!(value.pointer_remove("/user/emails/00"), None);
+        assert_eq!(value.pointer_remove("/user/name/x"), None);
+        assert_eq!(value.pointer_remove("/nope/x"), None);
+        assert_eq!(value.pointer_remove("user"), None);
+        assert_eq!(value.pointer_remove(""), None);
+
+        let mut user = value.remove("user").unwrap();
+        assert_eq!(user.remove("name"), Some(Value::String("a".to_string())));
+        assert_eq!(user.remove("name"), None);
+        assert_eq!(value.remove("user"), None);
+        assert_eq!(Value::Array(vec![]).remove("0"), None);
+    }
+
+    #[test]
+    fn test_exact_integers() {
+        assert_eq!(from_str::<i64>("9007199254740993").unwrap(), 9007199254740993);
+        assert_eq!(from_str::<i64>("-9007199254740995").unwrap(), -9007199254740995);
+        assert_eq!(from_str::<i64>("9223372036854775807").unwrap(), i64::MAX);
+        assert_eq!(from_str::<i64>("-9223372036854775808").unwrap(), i64::MIN);
+        assert_eq!(from_str::<u64>("18446744073709551615").unwrap(), u64::MAX);
+        assert_eq!(from_str::<u64>("12345678901234567891").unwrap(), 12345678901234567891);
+
+        // Exact integers survive a round trip through Value and text
+        for text in ["9007199254740993", "-9223372036854775808", "18446744073709551615"] {
+            assert_eq!(to_string(&parse(text).unwrap()).unwrap(), text);
+        }
+        assert_eq!(to_string(&(i64::MAX - 1)).unwrap(), "9223372036854775806");
+        let max = to_string(&u64::MAX).unwrap();
+        assert_eq!(max, "18446744073709551615");
+        assert_eq!(from_str::<u64>(&max).unwrap(), u64::MAX);
+        assert_eq!(to_string(&(u64::MAX as usize)).unwrap(), (u64::MAX as usize).to_string());
+
+        // Out of range for the target type, or beyond what any integer type holds
+        assert!(from_str::<i64>("9223372036854775808").is_err());
+        assert!(from_str::<u64>("18446744073709551616").is_err());
+        assert!(from_str::<u64>("-1").is_err());
+        assert!(from_str::<i32>("2147483648").is_err());
+
+        // Floats are still only accepted as integers while exactly representable
+        assert_eq!(from_str::<i32>("1e3").unwrap(), 1000);
+        assert_eq!(from_str::<u8>("5.0").unwrap(), 5);
+        assert!(from_str::<i64>("9007199254740993.0").is_err());
+        assert!(from_str::<i64>("1.5").is_err());
+
+        // Numbers compare numerically regardless of representation
+        assert_eq!(parse("2").unwrap(), Value::from(2.0));
+        assert_eq!(parse("-0").unwrap(), parse("0").unwrap());
+        assert_ne!(parse("9007199254740993").unwrap(), parse("9007199254740992").unwrap());
+    }
+
+    #[test]
+    fn test_pretty_trailing_newline() {
+        let data = vec![1, 2];
+        let plain = to_string_pretty(&data).unwrap();
+        assert!(!plain.ends_with('\n'));
+        assert_eq!(to_string_with(&data, &SerializeOptions::new().pretty()).unwrap(), plain);
+
+        let options = SerializeOptions::new().pretty().trailing_newline(true);
+        let with_newline = to_string_with(&data, &options).unwrap();
+        assert_eq!(with_newline, "[\n  1,\n  2\n]\n");
+        assert_eq!(to_string_with(&Value::Null, &options).unwrap(), "null\n");
+    }
+
+    #[test]
+    fn test_leading_zeros() {
+        for (input, position) in [("01", 0), ("00", 0), ("-007", 1), ("[1, 02]", 4)] {
+            match parse(input) {
+                Err(Error::Syntax { position: pos, message, .. }) => {
+                    assert_eq!(message, "leading zeros are not allowed", "{}", input);
+                    assert_eq!(pos, position, "{}", input);
+                }
+                other => panic!("expected syntax error for {}, got {:?}", input, other),
+            }
+        }
+
+        assert_eq!(parse("0.5").unwrap(), Value::from(0.5));
+        assert_eq!(parse("0e1").unwrap(), Value::from(0.0));
+        assert_eq!(parse("-0.25").unwrap(), Value::from(-0.25));
+        assert_eq!(parse("[0, 10]").unwrap(), Value::Array(vec![Value::from(0.0), Value::from(10.0)]));
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let data = vec![Value::String("héllo".to_string()), Value::Null];
+        assert_eq!(to_vec(&data).unwrap(), to_string(&data).unwrap().into_bytes());
+        assert_eq!(to_vec_pretty(&data).unwrap(), to_string_pretty(&data).unwrap().into_bytes());
+        assert_eq!(to_vec("x").unwrap(), b"\"x\"");
+        assert!(to_vec(&f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_non_finite_f32() {
+        for n in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(matches!(Serialize::serialize(&n), Err(Error::Custom(_))), "{}", n);
+            assert!(to_string(&n).is_err());
+        }
+        assert_eq!(to_string(&1.5f32).unwrap(), "1.5");
+        assert!(to_string(&vec![1.0f32, f32::NAN]).is_err());
+
+        // Converting into a Value maps them to null instead
+        assert_eq!(Value::from(f64::NAN), Value::Null);
+        assert_eq!(Value::from(f32::INFINITY), Value::Null);
+        assert_eq!(to_string(&Value::from(f64::NEG_INFINITY)).unwrap(), "null");
+        assert_eq!(Value::from(1.5f32), Value::from(1.5));
+    }
+
+    #[test]
+    fn test_f32_out_of_range() {
+        assert!(matches!(from_str::<f32>("-1e39"), Err(Error::TypeError(_))));
+        assert!(from_str::<Vec<f32>>("[1.0, 3.5e38]").is_err());
+        match from_str::<f32>("1e39") {
+            Err(Error::TypeError(msg)) => assert_eq!(msg, "value 1e39 out of range for f32"),
+            other => panic!("expected type error, got {:?}", other),
+        }
+        assert_eq!(from_str::<f32>("3.4e38").unwrap(), 3.4e38);
+        assert_eq!(from_str::<f32>("1e-50").unwrap(), 0.0);
+        let max = to_string(&f32::MAX).unwrap();
+        assert_eq!(from_str::<f32>(&max).unwrap(), f32::MAX);
+    }
+
+    #[test]
+    fn test_seq_collections() {
+        use std::collections::{LinkedList, VecDeque};
+
+        let mut queue: VecDeque<u32> = VecDeque::new();
+        queue.push_back(2);
+        queue.push_back(3);
+        queue.push_front(1);
+        let json = to_string(&queue).unwrap();
+        assert_eq!(json, "[1, 2, 3]");
+        assert_eq!(from_str::<VecDeque<u32>>(&json).unwrap(), queue);
+
+        let list: LinkedList<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let json = to_string(&list).unwrap();
+        assert_eq!(json, r#"["a", "b"]"#);
+        assert_eq!(from_str::<LinkedList<String>>(&json).unwrap(), list);
+
+        assert!(from_str::<VecDeque<u32>>("{}").is_err());
+    }
+
+    #[test]
+    fn test_type_name() {
+        let value = parse(r#"[null, true, 1.5, "s", [], {}]"#).unwrap();
+        let names: Vec<&str> = value.as_array().unwrap().iter().map(Value::type_name).collect();
+        assert_eq!(names, ["null", "bool", "number", "string", "array", "object"]);
+
+        // Type errors name the kind of value found rather than dumping it
+        match from_str::<Vec<u32>>(r#"{"big": [1, 2, 3]}"#) {
+            Err(Error::TypeError(msg)) => assert_eq!(msg, "expected array, found object"),
+            other => panic!("expected type error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_whitespace_between_tokens() {
+        let crlf = "{\r\n  \"a\" : 1,\r\n  \"b\"\r\n:\r\n[\r\n1\r\n,\r\n2\r\n]\r\n,\r\n\"c\":{\r\n}\r\n}\r\n";
+        let value = parse(crlf).unwrap();
+        assert_eq!(value.get("a"), Some(&Value::from(1)));
+        assert_eq!(value.get("b").unwrap().as_array().unwrap().len(), 2);
+        assert_eq!(value.get("c"), Some(&Value::Object(Map::new())));
+
+        let tabs = "\t{\t\"a\"\t:\t[\t]\t,\t\"b\"\t:\t{\t\"c\"\t:\tnull\t}\t}\t";
+        let value = parse(tabs).unwrap();
+        assert_eq!(value.get("a").unwrap().as_array().unwrap().len(), 0);
+        assert!(value.get("b").unwrap().get("c").unwrap().is_null());
+
+        // Every JSON whitespace character, between every pair of tokens
+        let mixed = " \t\r\n[ \t\r\n{ \t\r\n\"k\" \t\r\n: \t\r\ntrue \t\r\n} \t\r\n, \t\r\n\"s\" \t\r\n] \t\r\n";
+        assert_eq!(parse(mixed).unwrap().as_array().unwrap().len(), 2);
+
+        // Other Unicode whitespace is not JSON whitespace
+        for ws in ['\u{00A0}', '\u{2028}', '\u{2029}', '\u{000B}', '\u{000C}', '\u{3000}'] {
+            assert!(parse(&format!("{}1", ws)).is_err(), "{:?}", ws);
+            assert!(parse(&format!("[1,{}2]", ws)).is_err(), "{:?}", ws);
+            assert!(parse(&format!("{{\"a\":1{}}}", ws)).is_err(), "{:?}", ws);
+            assert!(parse(&format!("1{}", ws)).is_err(), "{:?}", ws);
+        }
+    }
+
+    #[test]
+    fn test_serialize_options() {
+        let mut map = Map::new();
+        map.insert("b".to_string(), Value::Array(vec![Value::from(1), Value::from("ü")]));
+        map.insert("a".to_string(), Value::Object(Map::new()));
+        let value = Value::Object(map);
+
+        // The presets are the default and pretty options
+        assert_eq!(to_string_with(&value, &SerializeOptions::new()).unwrap(), to_string(&value).unwrap());
+        assert_eq!(to_string_with(&value, &SerializeOptions::new().pretty()).unwrap(), to_string_pretty(&value).unwrap());
+
+        let compact = SerializeOptions::new().sort_keys(true).escape_non_ascii(true);
+        assert_eq!(to_string_with(&value, &compact).unwrap(), r#"{"a": {}, "b": [1, "\u00fc"]}"#);
+
+        let options = SerializeOptions::new().indent(4).sort_keys(true).escape_non_ascii(true).trailing_newline(true);
+        assert_eq!(
+            to_string_with(&value, &options).unwrap(),
+            "{\n    \"a\": {},\n    \"b\": [\n        1,\n        \"\\u00fc\"\n    ]\n}\n"
+        );
+
+        // Nested maps are sorted too, including inside sorted entries
+        let nested = parse(r#"{"z": {"y": [{"b": 1, "a": {"d": 2, "c": 3}}], "x": null}, "m": [], "a": {}}"#).unwrap();
+        let sorted = to_string_with(&nested, &SerializeOptions::new().sort_keys(true)).unwrap();
+        assert_eq!(sorted.replace(' ', ""), nested.to_canonical_string());
+        let pretty = to_string_with(&nested, &SerializeOptions::new().pretty().sort_keys(true)).unwrap();
+        assert_eq!(parse(&pretty).unwrap(), nested);
+        assert!(pretty.starts_with("{\n  \"a\": {},\n  \"m\": [],\n  \"z\": {\n    \"x\": null,\n    \"y\": [\n      {\n        \"a\": {\n          \"c\": 3,"));
+
+        // Characters outside the BMP become surrogate pairs, and the output parses back
+        let emoji = to_string_with(&"😀", &compact).unwrap();
+        assert_eq!(emoji, r#""\ud83d\ude00""#);
+        assert_eq!(from_str::<String>(&emoji).unwrap(), "😀");
+    }
+
+    #[test]
+    fn test_surrogate_pair_escapes() {
+        assert_eq!(from_str::<String>(r#""\ud83d\ude00!""#).unwrap(), "😀!");
+        assert_eq!(from_str::<String>(r#""\uD834\uDD1E""#).unwrap(), "𝄞");
+
+        // Unpaired surrogates are not valid characters
+        assert!(from_str::<String>(r#""\ud83d""#).is_err());
+        assert!(from_str::<String>(r#""\ud83dx""#).is_err());
+        assert!(from_str::<String>(r#""\ud83d\u0041""#).is_err());
+        assert!(from_str::<String>(r#""\ude00""#).is_err());
+    }
+
+    #[test]
+    fn test_recover_array_elements() {
+        let input = r#"[{"level": "info"}, {"level": tru}, 3, @, "bad \q escape", [1, [2, }], {"ok": [nul]}, 4]"#;
+
+        // Off by default
+        assert!(parse(input).is_err());
+
+        let options = ParserOptions { recover_array_elements: true, ..ParserOptions::default() };
+        let (value, errors) = parse_with_errors(input, &options).unwrap();
+        let items = value.as_array().unwrap();
+        assert_eq!(items.len(), 5);
+        assert_eq!(items[1], Value::from(3));
+        assert_eq!(items[2].as_array().unwrap().len(), 1); // [1, <skipped>]
+        assert_eq!(items[3].get("ok").unwrap().as_array().unwrap().len(), 0);
+        assert_eq!(items[4], Value::from(4));
+        assert_eq!(errors.len(), 5);
+        assert!(errors.iter().all(|err| matches!(err, Error::Syntax { snippet: Some(_), .. })));
+
+        // The array's own structure still has to be valid
+        for input in [r#"[1 2]"#, r#"[1, ]"#, r#"[1, tru"#, r#"{"a": tru, "b": [1]}"#, r#"[{"a": }}]"#] {
+            assert!(parse_with_errors(input, &options).is_err(), "{}", input);
+        }
+
+        let (numbers, errors) = from_str_lenient::<Vec<Vec<u32>>>("[[1, x, 2], [3]]").unwrap();
+        assert_eq!(numbers, vec![vec![1, 2], vec![3]]);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_result() {
+        let ok: std::result::Result<Vec<u32>, String> = Ok(vec![1, 2]);
+        let json = to_string(&ok).unwrap();
+        assert_eq!(json, r#"{"Ok": [1, 2]}"#);
+        assert_eq!(from_str::<std::result::Result<Vec<u32>, String>>(&json).unwrap(), ok);
+
+        let err: std::result::Result<Vec<u32>, String> = Err("timeout".to_string());
+        let json = to_string(&err).unwrap();
+        assert_eq!(json, r#"{"Err": "timeout"}"#);
+        assert_eq!(from_str::<std::result::Result<Vec<u32>, String>>(&json).unwrap(), err);
+
+        // The streaming path writes the same shape
+        let mut serializer = JsonSerializer::new();
+        err.serialize_to(&mut serializer).unwrap();
+        assert_eq!(serializer.into_string(), json);
+
+        // Exactly one of the two keys is required
+        for input in [r#"{}"#, r#"{"Ok": 1, "Err": "x"}"#, r#"{"ok": 1}"#, r#"[1]"#] {
+            assert!(from_str::<std::result::Result<u32, String>>(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_escape_forward_slash() {
+        let plain = to_string(&"a/b").unwrap();
+        assert_eq!(plain, r#""a/b""#);
+
+        let options = SerializeOptions::new().escape_forward_slash(true);
+        let escaped = to_string_with(&"a/b", &options).unwrap();
+        assert_eq!(escaped, r#""a\/b""#);
+        assert_eq!(to_string_with(&"</script>", &options).unwrap(), r#""<\/script>""#);
+
+        assert_eq!(from_str::<String>(&plain).unwrap(), "a/b");
+        assert_eq!(from_str::<String>(&escaped).unwrap(), "a/b");
+
+        // Combines with the other escaping options
+        let both = options.escape_non_ascii(true);
+        assert_eq!(to_string_with(&"é/", &both).unwrap(), r#""\u00e9\/""#);
+    }
+
+    #[test]
+    fn test_number_trailing_garbage() {
+        let cases = [
+            ("42abc", 2, "unexpected character 'a' after number 42"),
+            ("1.2.3", 3, "unexpected character '.' after number 1.2"),
+            ("1e5e5", 3, "unexpected character 'e' after number 1e5"),
+            ("0x10", 1, "unexpected character 'x' after number 0"),
+            ("[7_000]", 2, "unexpected character '_' after number 7"),
+            (r#"{"n": 3.5f}"#, 9, "unexpected character 'f' after number 3.5"),
+            ("1.", 2, "expected digit after decimal point"),
+            ("1.e3", 2, "expected digit after decimal point"),
+            ("2e+", 3, "expected digit in exponent"),
+            ("-x", 1, "expected digit"),
+            ("1 2", 2, "trailing character '2' after JSON value"),
+        ];
+        for (input, expected_position, expected_message) in cases {
+            match from_str::<Value>(input) {
+                Err(Error::Syntax { position, message, .. }) => {
+                    assert_eq!((position, message.as_str()), (expected_position, expected_message), "{}", input);
+                }
+                other => panic!("expected syntax error for {}, found {:?}", input, other),
+            }
+        }
+
+        assert!(from_str::<i32>("42abc").is_err());
+        assert!(from_str::<f64>("1.2.3").is_err());
+        assert_eq!(from_str::<f64>(" 1.25 ").unwrap(), 1.25);
+        assert_eq!(from_str::<Vec<i32>>("[1,-2]").unwrap(), vec![1, -2]);
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut doc = parse(r#"{"user": {"name": "a", "tokens": ["s1", "s2"]}, "ids": [1, 2, 3], "ok": true}"#).unwrap();
+
+        let mut nodes = 0;
+        doc.walk(&mut |_| nodes += 1);
+        // root, user, name, tokens, s1, s2, ids, 1, 2, 3, ok
+        assert_eq!(nodes, 11);
+
+        let mut strings = Vec::new();
+        doc.walk(&mut |v| strings.extend(v.as_str().map(str::to_string)));
+        strings.sort();
+        assert_eq!(strings, ["a", "s1", "s2"]);
+
+        // Parents are visited first, so walk order starts at the root
+        let mut first = None;
+        doc.walk(&mut |v| { first.get_or_insert_with(|| v.type_name()); });
+        assert_eq!(first, Some("object"));
+
+        doc.walk_mut(&mut |v| {
+            if v.is_string() {
+                *v = Value::from("***");
+            }
+        });
+        assert_eq!(
+            doc.to_canonical_string(),
+            r#"{"ids":[1,2,3],"ok":true,"user":{"name":"***","tokens":["***","***"]}}"#
+        );
+    }
+
+    #[test]
+    fn test_leading_bom() {
+        assert_eq!(parse("\u{FEFF}{}").unwrap(), Value::Object(Map::new()));
+        assert_eq!(from_str::<Vec<u8>>("\u{FEFF} [1]").unwrap(), vec![1]);
+
+        // Only a BOM at the very start is skipped; positions still count it
+        assert!(parse(" \u{FEFF}{}").is_err());
+        assert!(parse("[\u{FEFF}1]").is_err());
+        match parse("\u{FEFF}[1,]") {
+            Err(Error::Syntax { position, .. }) => assert_eq!(position, 6),
+            other => panic!("expected syntax error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let ndjson = "{\"level\": \"info\", \"n\": 1}\n{\"level\": \"warn\", \"n\": 2}\r\n\n{\"level\": \"error\", \"n\": 3}\n";
+        let values = parse_many(ndjson).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[2].get("level").unwrap().as_str(), Some("error"));
+
+        let numbers: Vec<i32> = from_str_many("1 2\t3\n-4").unwrap();
+        assert_eq!(numbers, vec![1, 2, 3, -4]);
+        assert_eq!(parse_many(r#"[1]{"a":true}"x"null"#).unwrap().len(), 4);
+        assert!(parse_many("  \n ").unwrap().is_empty());
+
+        // A malformed value fails the whole sequence, with its position in the input
+        match parse_many("{}\n{\"a\": }\n{}") {
+            Err(Error::Syntax { position, .. }) => assert_eq!(position, 9),
+            other => panic!("expected syntax error, found {:?}", other),
+        }
+        assert!(from_str_many::<i32>("1 \"two\"").is_err());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let range = 0usize..10;
+        let value = range.serialize().unwrap();
+        assert_eq!(value.to_canonical_string(), r#"{"end":10,"start":0}"#);
+        assert_eq!(from_str::<std::ops::Range<usize>>(&to_string(&range).unwrap()).unwrap(), range);
+
+        let inclusive = 0u8..=10;
+        assert_eq!(inclusive.serialize().unwrap().to_canonical_string(), r#"{"end":10,"start":0}"#);
+        assert_eq!(from_str::<std::ops::RangeInclusive<u8>>(&to_string(&inclusive).unwrap()).unwrap(), inclusive);
+
+        assert_eq!(
+            from_str::<std::ops::Range<usize>>(r#"{"start": 1}"#).unwrap_err(),
+            Error::MissingField("end".to_string())
+        );
+        assert!(from_str::<std::ops::Range<usize>>("[0, 10]").is_err());
+    }
+
+    #[test]
+    fn test_as_scalar_string() {
+        assert_eq!(Value::from("a \"quoted\" word").as_scalar_string().as_deref(), Some("a \"quoted\" word"));
+        assert_eq!(Value::from(true).as_scalar_string().as_deref(), Some("true"));
+        assert_eq!(Value::Null.as_scalar_string().as_deref(), Some("null"));
+        assert_eq!(Value::from(42).as_scalar_string().as_deref(), Some("42"));
+        assert_eq!(Value::from(2.0).as_scalar_string().as_deref(), Some("2.0"));
+        assert_eq!(Value::from(-0.5).as_scalar_string().as_deref(), Some("-0.5"));
+        assert_eq!(Value::from(1e300).as_scalar_string().as_deref(), Some("1e300"));
+        assert_eq!(Value::from(u64::MAX).as_scalar_string().as_deref(), Some("18446744073709551615"));
+        assert_eq!(Value::Array(vec![]).as_scalar_string(), None);
+        assert_eq!(Value::Object(Map::new()).as_scalar_string(), None);
+    }
+
+    #[test]
+    fn test_map_order() {
+        // Parsed objects keep document order
+        let json = r#"{"z": 1, "a": 2, "m": {"y": true, "b": null}}"#;
+        assert_eq!(to_string(&parse(json).unwrap()).unwrap(), json);
+
+        let mut map = Map::new();
+        for key in ["c", "a", "b"] {
+            map.insert(key.to_string(), Value::from(key));
+        }
+        // Replacing keeps the position, removing keeps the rest in order
+        assert_eq!(map.insert("a".to_string(), Value::Null), Some(Value::from("a")));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["c", "a", "b"]);
+        assert_eq!(map.remove("c"), Some(Value::from("c")));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b"]);
+        *map.entry("d").or_insert(Value::from(0)) = Value::from(4);
+        assert_eq!(to_string(&map).unwrap(), r#"{"a": null, "b": "b", "d": 4}"#);
+
+        // Large objects switch to an index; lookups and order still hold
+        let mut big: Map = (0..40).map(|i| (format!("k{}", i), Value::from(i))).collect();
+        assert_eq!(big.get("k33"), Some(&Value::from(33)));
+        big.remove("k0");
+        big.retain(|key, _| key != "k20");
+        assert_eq!(big.len(), 38);
+        assert_eq!(big.get("k21"), Some(&Value::from(21)));
+        assert!(!big.contains_key("k20"));
+        assert_eq!(big.keys().next().unwrap(), "k1");
+
+        // Removing updates the index in place, down past the threshold
+        for i in (1..40).step_by(3).chain((2..40).step_by(3)) {
+            big.remove(&format!("k{}", i));
+            for (position, (key, value)) in big.iter().enumerate() {
+                assert_eq!(big.get(key), Some(value), "{} at {}", key, position);
+            }
+        }
+        assert_eq!(big.keys().collect::<Vec<_>>(), ["k3", "k6", "k9", "k12", "k15", "k18", "k21", "k24", "k27", "k30", "k33", "k36", "k39"]);
+        if let crate::map::Entry::Occupied(entry) = big.entry("k3") {
+            entry.remove();
+        }
+        assert_eq!(big.get("k39"), Some(&Value::from(39)));
+
+        // Equality ignores order
+        let reversed: Map = map.clone().into_iter().rev().collect();
+        assert_eq!(reversed, map);
+        assert_ne!(to_string(&reversed).unwrap(), to_string(&map).unwrap());
+    }
+
+    #[test]
+    fn test_number_limits() {
+        // Overflowing magnitudes are errors rather than infinity
+        for json in ["1e400", "-1e400", "[1.5e309]", &"9".repeat(400)] {
+            match parse(json) {
+                Err(Error::Syntax { message, .. }) => assert!(message.starts_with("number out of range"), "{}", message),
+                other => panic!("expected range error for {}, got {:?}", json, other),
+            }
+        }
+        // Underflow to zero is fine, as is the largest finite f64
+        assert_eq!(parse("1e-400").unwrap(), Value::from(0.0));
+        assert_eq!(parse("1.7976931348623157e308").unwrap(), Value::from(f64::MAX));
+
+        let long = format!("0.{}", "1".repeat(100_000));
+        match parse(&format!("[{}]", long)) {
+            Err(Error::LimitExceeded { limit, position }) => {
+                assert_eq!(limit, "max_number_length");
+                assert_eq!(position, 1);
+            }
+            other => panic!("expected length error, got {:?}", other),
+        }
+        let relaxed = ParserOptions { max_number_length: 200_000, ..ParserOptions::default() };
+        assert!(parse_with_options(&long, &relaxed).is_ok());
+        let strict = ParserOptions { max_number_length: 3, ..ParserOptions::default() };
+        assert!(parse_with_options("123", &strict).is_ok());
+        assert!(parse_with_options("-123", &strict).is_err());
+    }
+
+    #[test]
+    fn test_value_to_strings() {
+        let value = parse(r#"{"name": "é\n", "tags": [1, 2.5, null], "empty": {}, "nested": {"ok": true}}"#).unwrap();
+        assert_eq!(value.to_compact_string(), to_string(&value).unwrap());
+        assert_eq!(value.to_pretty_string(), to_string_pretty(&value).unwrap());
+        assert_eq!(
+            value.to_compact_string(),
+            r#"{"name": "é\n", "tags": [1, 2.5, null], "empty": {}, "nested": {"ok": true}}"#
+        );
+        assert_eq!(
+            value.to_pretty_string(),
+            "{\n  \"name\": \"é\\n\",\n  \"tags\": [\n    1,\n    2.5,\n    null\n  ],\n  \"empty\": {},\n  \"nested\": {\n    \"ok\": true\n  }\n}"
+        );
+        assert_eq!(Value::Null.to_pretty_string(), "null");
+        assert_eq!(Value::Array(Vec::new()).to_compact_string(), "[]");
+        assert_eq!(Value::Array(vec![Value::from(f64::NAN), Value::from(f32::INFINITY)]).to_compact_string(), "[null, null]");
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        assert_eq!(parse(r#""a\/b""#).unwrap(), Value::from("a/b"));
+        assert_eq!(
+            parse(r#""\"\\\/\b\f\n\r\tAé""#).unwrap(),
+            Value::from("\"\\/\u{8}\u{c}\n\r\tAé")
+        );
+
+        // Every escape any output option can write parses back to the same text
+        let mut text: String = (0u8..0x80).map(char::from).collect();
+        text.push_str("é\u{2028}\u{fffd}😀");
+        let outputs = [
+            SerializeOptions::new(),
+            SerializeOptions::new().escape_non_ascii(true),
+            SerializeOptions::new().escape_forward_slash(true),
+            SerializeOptions::new().escape_js_separators(true),
+            SerializeOptions::new().escape_non_ascii(true).escape_forward_slash(true).pretty(),
+        ];
+        for options in &outputs {
+            let json = to_string_with(&text, options).unwrap();
+            assert_eq!(from_str::<String>(&json).unwrap(), text, "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_float_precision() {
+        let two = SerializeOptions::new().float_precision(2);
+        assert_eq!(to_string_with(&1.5, &two).unwrap(), "1.50");
+        assert_eq!(to_string_with(&0.125, &two).unwrap(), "0.12");
+        assert_eq!(to_string_with(&-2.0, &two).unwrap(), "-2.00");
+        assert_eq!(to_string_with(&1e21, &two).unwrap(), "1000000000000000000000.00");
+
+        // Integers stay exact, and the default keeps shortest round-trip digits
+        assert_eq!(to_string_with(&vec![1.0, 2.25], &two).unwrap(), "[1.00, 2.25]");
+        assert_eq!(to_string_with(&7u32, &two).unwrap(), "7");
+        assert_eq!(to_string(&1.5).unwrap(), "1.5");
+
+        let zero = SerializeOptions::new().float_precision(0).pretty();
+        assert_eq!(to_string_with(&vec![2.5, 3.7], &zero).unwrap(), "[\n  2,\n  4\n]");
+    }
+
+    #[test]
+    fn test_take_key() {
+        let rows: Vec<Value> = (0..10_000).map(|i| Value::Array(vec![Value::from(i), Value::from("x")])).collect();
+        let mut value = Value::Null;
+        value.insert("id", 1).unwrap();
+        value.insert("rows", Value::Array(rows.clone())).unwrap();
+        value.insert("tail", true).unwrap();
+
+        let taken = value.take_key("rows").unwrap();
+        assert_eq!(taken, Value::Array(rows));
+
+        // The key is left holding null, in the same place
+        assert_eq!(value.get("rows"), Some(&Value::Null));
+        assert_eq!(value.to_compact_string(), r#"{"id": 1, "rows": null, "tail": true}"#);
+
+        assert_eq!(value.take_key("missing"), None);
+        assert_eq!(Value::from(1).take_key("rows"), None);
+    }
+
+    #[test]
+    fn test_number_source_text() {
+        // Literals come back exactly as written, through every output path
+        let json = "[0.1, 0.10, 1.000000000000000000001, 1E+2, -0, 18446744073709551616, 7]";
+        let value = parse(json).unwrap();
+        assert_eq!(to_string(&value).unwrap(), json);
+        assert_eq!(value.to_compact_string(), json);
+        assert_eq!(value.to_string(), json);
+        let mut serializer = JsonSerializer::new();
+        value.serialize_to(&mut serializer).unwrap();
+        assert_eq!(serializer.into_string(), json);
+
+        let texts: Vec<String> = value.as_array().unwrap().iter().map(|v| v.as_number_str().unwrap().into_owned()).collect();
+        assert_eq!(texts, ["0.1", "0.10", "1.000000000000000000001", "1E+2", "-0", "18446744073709551616", "7"]);
+        assert_eq!(Value::from(2.5).as_number_str().as_deref(), Some("2.5"));
+        assert_eq!(Value::from("0.1").as_number_str(), None);
+
+        // Arithmetic and equality still go through the numeric value
+        let items = value.as_array().unwrap();
+        assert_eq!(items[1], items[0]);
+        assert_eq!(items[3].as_f64(), Some(100.0));
+        assert_eq!(from_str::<f64>("0.10").unwrap(), 0.1);
+
+        // Canonical output normalizes the text so equal numbers agree
+        assert_eq!(parse("[1E+2, 0.10]").unwrap().to_canonical_string(), "[100,0.1]");
+        assert_eq!(Value::from(100.0).to_canonical_string(), Value::from(100).to_canonical_string());
+    }
+
+    #[test]
+    fn test_from_str_checked_warnings() {
+        let options = ParserOptions::default();
+        let (n, warnings): (i64, _) = from_str_checked("3.0", &options).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(warnings, vec![Warning::IntegerFromFloat { value: "3.0".to_string(), ty: "i64" }]);
+
+        let (_, warnings): (f32, _) = from_str_checked("16777217", &options).unwrap();
+        assert_eq!(warnings, vec![Warning::PrecisionLoss { value: "16777217".to_string(), ty: "f32" }]);
+        let (_, warnings): (f64, _) = from_str_checked("9007199254740993", &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        // Exactly representable values, including ones that only round-trip
+        // through their shortest text, raise no warnings
+        let (values, warnings): (Vec<f64>, _) = from_str_checked("[0.1, 1.50e2, 0, -2.5, 1e-7]", &options).unwrap();
+        assert_eq!(values, vec![0.1, 150.0, 0.0, -2.5, 1e-7]);
+        assert!(warnings.is_empty());
+        let (_, warnings): (f32, _) = from_str_checked("0.1", &options).unwrap();
+        assert!(warnings.is_empty());
+
+        let coerce = ParserOptions { coerce_numbers_from_strings: true, ..ParserOptions::default() };
+        let (n, warnings): (u8, _) = from_str_checked("\"30\"", &coerce).unwrap();
+        assert_eq!(n, 30);
+        assert_eq!(warnings[0].to_string(), "string \"30\" was converted to u8");
+
+        // Plain deserialization never collects warnings
+        assert_eq!(from_str::<i64>("3.0").unwrap(), 3);
+        let (_, warnings): (i64, _) = from_str_checked("3", &options).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_byte_buffer_streaming() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let json = to_string(&bytes).unwrap();
+        assert!(json.starts_with("[0, 1, 2, "));
+        assert!(json.ends_with(", 254, 255]"));
+        // The streamed compact output matches the Value-based writer
+        assert_eq!(json, to_string_with(&bytes, &SerializeOptions::new()).unwrap());
+        assert_eq!(from_str::<Vec<u8>>(&json).unwrap(), bytes);
+        assert_eq!(to_string(&Vec::<u8>::new()).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_value_entry() {
+        let mut value = Value::Null;
+
+        // Vacant: Null is promoted and the default inserted
+        match value.entry("hits").unwrap() {
+            map::Entry::Vacant(e) => assert_eq!(e.key(), "hits"),
+            map::Entry::Occupied(_) => panic!("expected a vacant entry"),
+        }
+        *value.entry("hits").unwrap().or_insert(Value::from(0)) = Value::from(1);
+        assert_eq!(value.to_compact_string(), r#"{"hits": 1}"#);
+
+        // Occupied: the existing value is kept and can be modified in place
+        let hits = value.entry("hits").unwrap().and_modify(|v| *v = Value::from(2)).or_insert(Value::from(0));
+        assert_eq!(hits.as_f64(), Some(2.0));
+        if let map::Entry::Occupied(mut e) = value.entry("hits").unwrap() {
+            assert_eq!(e.insert(Value::from(5)).as_f64(), Some(2.0));
+            assert_eq!(e.remove().as_f64(), Some(5.0));
+        }
+        assert_eq!(value.to_compact_string(), "{}");
+
+        value.entry("first").unwrap().or_default();
+        value.entry("second").unwrap().or_insert_with(|| Value::from("x"));
+        assert_eq!(value.to_compact_string(), r#"{"first": null, "second": "x"}"#);
+
+        // Other non-objects are an error and stay unchanged
+        let mut array = Value::Array(vec![]);
+        assert!(array.entry("k").is_err());
+        assert_eq!(array, Value::Array(vec![]));
+    }
+
+    #[test]
+    fn test_deserialize_static_str() {
+        const UNITS: &[&str] = &["ms", "s", "min"];
+
+        let unit = deserialize_static_str(Value::from("s"), UNITS).unwrap();
+        assert_eq!(unit, "s");
+        assert!(std::ptr::eq(unit, UNITS[1]));
+
+        let err = deserialize_static_str(Value::from("h"), UNITS).unwrap_err();
+        assert_eq!(err.to_string(), r#"Type error: unknown value "h", expected one of ["ms", "s", "min"]"#);
+        assert!(deserialize_static_str(Value::from(1), UNITS).is_err());
+    }
+
+    #[test]
+    fn test_get_ci() {
+        let value = parse(r#"{"ID": 7, "Name": "a", "name": "b", "ÉTÉ": true}"#).unwrap();
+        assert_eq!(value.get_ci("id"), Some(&Value::from(7)));
+        assert_eq!(value.get_ci("iD"), Some(&Value::from(7)));
+        // An exact match is preferred over an earlier case-insensitive one
+        assert_eq!(value.get_ci("name"), Some(&Value::from("b")));
+        assert_eq!(value.get_ci("NAME"), Some(&Value::from("a")));
+        assert_eq!(value.get_ci("été"), Some(&Value::Bool(true)));
+        assert_eq!(value.get_ci("missing"), None);
+        assert_eq!(Value::from(1).get_ci("id"), None);
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+        assert_eq!(parse(&deep), Err(Error::LimitExceeded { limit: "max_depth", position: 128 }));
+
+        let objects = format!("{}1{}", r#"{"a": "#.repeat(129), "}".repeat(129));
+        match parse(&objects) {
+            Err(err @ Error::LimitExceeded { .. }) => {
+                assert_eq!(err.to_string(), "Limit max_depth exceeded at position 768");
+            }
+            other => panic!("expected depth limit, got {:?}", other),
+        }
+
+        // Exactly at the limit is fine, including an empty innermost container
+        let at_limit = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert!(parse(&at_limit).is_ok());
+        let shallow = ParserOptions { max_depth: 2, ..ParserOptions::default() };
+        assert!(parse_with_options("[{}]", &shallow).is_ok());
+        assert!(parse_with_options(r#"[{"a": []}]"#, &shallow).is_err());
+        let relaxed = ParserOptions { max_depth: 1000, ..ParserOptions::default() };
+        assert!(parse_with_options(&deep, &relaxed).is_ok());
+
+        // Syntax errors stay distinct from limit errors
+        assert!(matches!(parse("[1,]"), Err(Error::Syntax { .. })));
+    }
+
+    #[test]
+    fn test_to_string_infallible() {
+        assert_eq!(to_string_infallible("a\"b"), r#""a\"b""#);
+        assert_eq!(to_string_infallible(&true), "true");
+        assert_eq!(to_string_infallible(&vec![Some(1u64), None]), "[1, null]");
+        assert_eq!(to_string_infallible(&u128::MAX), to_string(&u128::MAX).unwrap());
+        // Every integer type is exact, including u64 values beyond 2^53
+        assert_eq!(to_string_infallible(&u64::MAX), "18446744073709551615");
+        assert_eq!(to_string_infallible(&usize::MAX), usize::MAX.to_string());
+
+        let value = parse(r#"{"a": [1.5, "x"]}"#).unwrap();
+        assert_eq!(to_string_infallible(&value), r#"{"a": [1.5, "x"]}"#);
+
+        let mut map = HashMap::new();
+        map.insert(1, vec!["x"]);
+        assert_eq!(to_string_infallible(&map), r#"{"1": ["x"]}"#);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        for json in ["", "   ", "\n\t\r\n"] {
+            assert_eq!(parse(json), Err(Error::EmptyInput), "{:?}", json);
+        }
+        assert_eq!(Error::EmptyInput.to_string(), "Empty JSON input");
+        assert_eq!(from_str::<Option<u8>>(" "), Err(Error::EmptyInput));
+
+        // Truncated documents are still end-of-input errors
+        assert_eq!(parse("[1, "), Err(Error::Eof));
+        assert!(parse_many("  ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reusable_parser() {
+        let mut parser = ReusableParser::new();
+        let mut scratch = Value::Null;
+        let docs = [
+            r#"{"id": 1, "tags": ["a", "b"], "nested": {"k": "v"}}"#,
+            r#"[1, "two", [3], {}]"#,
+            r#"{"id": 2, "tags": [], "nested": {"k": "w", "x": null}}"#,
+        ];
+        for _ in 0..3 {
+            for json in docs {
+                parser.parse_into(json, &mut scratch).unwrap();
+                assert_eq!(scratch, parse(json).unwrap());
+            }
+        }
+
+        // Errors match plain parsing and leave the scratch value as null
+        assert_eq!(parser.parse_into("[1,", &mut scratch), Err(Error::Eof));
+        assert_eq!(scratch, Value::Null);
+        assert_eq!(parser.parse("  "), Err(Error::EmptyInput));
+
+        // Recycled values are reusable buffers, never stale contents
+        let value = parser.parse(r#"{"a": "long string contents"}"#).unwrap();
+        parser.recycle(value);
+        assert_eq!(parser.parse(r#"{"b": ""}"#).unwrap(), parse(r#"{"b": ""}"#).unwrap());
+
+        let strict = ParserOptions { deny_duplicate_keys: true, ..ParserOptions::default() };
+        let mut parser = ReusableParser::with_options(strict);
+        assert!(parser.parse(r#"{"a": 1, "a": 2}"#).is_err());
+    }
+
+    #[test]
+    fn test_value_retain() {
+        let mut value = parse(r#"{"a": 1, "b": null, "c": "x", "d": null}"#).unwrap();
+        value.retain_entries(|_, v| !v.is_null());
+        assert_eq!(value.to_compact_string(), r#"{"a": 1, "c": "x"}"#);
+        value.retain_entries(|k, _| k != "a");
+        assert_eq!(value.to_compact_string(), r#"{"c": "x"}"#);
+
+        let mut array = parse(r#"[1, null, 2, null]"#).unwrap();
+        array.retain_elements(|v| !v.is_null());
+        assert_eq!(array.to_compact_string(), "[1, 2]");
+
+        // Mismatched and scalar values are left alone
+        array.retain_entries(|_, _| false);
+        assert_eq!(array.to_compact_string(), "[1, 2]");
+        let mut scalar = Value::from("s");
+        scalar.retain_elements(|_| false);
+        assert_eq!(scalar, Value::from("s"));
+    }
+
+    #[test]
+    fn test_max_input_len() {
+        let capped = ParserOptions { max_input_len: Some(16), ..ParserOptions::default() };
+        assert!(parse_with_options(r#"{"a": [1, 2]}"#, &capped).is_ok());
+        assert_eq!(
+            parse_with_options(r#"{"a": [1, 2, 3, 4]}"#, &capped),
+            Err(Error::LimitExceeded { limit: "max_input_len", position: 16 })
+        );
+        // Rejected before parsing, so even malformed input reports the size
+        let garbage = "x".repeat(1_000);
+        assert!(matches!(
+            parse_with_options(&garbage, &capped),
+            Err(Error::LimitExceeded { limit: "max_input_len", .. })
+        ));
+        assert!(from_str_with_options::<Vec<u8>>("[1, 2, 3, 4, 5, 6, 7]", &capped).is_err());
+        assert!(ReusableParser::with_options(capped).parse(&garbage).is_err());
+
+        // No limit by default
+        assert!(parse(&format!("[{}]", "1,".repeat(100_000) + "1")).is_ok());
+    }
+
+    #[test]
+    fn test_serialize_iter() {
+        let squares = serialize_iter((0..5).map(|x| x * x)).unwrap();
+        assert_eq!(squares.to_compact_string(), "[0, 1, 4, 9, 16]");
+
+        let words = ["a", "b"];
+        let value = serialize_iter(words.iter().rev()).unwrap();
+        assert_eq!(value.to_compact_string(), r#"["b", "a"]"#);
+        assert_eq!(serialize_iter(std::iter::empty::<u8>()).unwrap(), Value::Array(vec![]));
+
+        // The first failing item stops serialization
+        assert!(serialize_iter([1.0, f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_backslash_escapes() {
+        // An escaped quote is content, not the end of the string
+        assert_eq!(parse(r#""\"""#).unwrap(), Value::from("\""));
+        // An escaped backslash does not escape the closing quote
+        assert_eq!(parse(r#""\\""#).unwrap(), Value::from("\\"));
+        assert_eq!(parse(r#""a\\\"b""#).unwrap(), Value::from("a\\\"b"));
+        assert_eq!(parse(r#"["\\", "x"]"#).unwrap(), Value::Array(vec![Value::from("\\"), Value::from("x")]));
+
+        // Input ending inside an escape, or right after an escaped quote or backslash
+        for json in [r#""\"#, r#""abc\"#, r#""\""#, r#""\\"#, r#""\\\""#] {
+            assert_eq!(parse(json), Err(Error::Eof), "{}", json);
+        }
+
+        // An unknown escape is reported at the character after the backslash
+        match parse(r#""ab\q""#) {
+            Err(Error::Syntax { position, .. }) => assert_eq!(position, 4),
+            other => panic!("expected syntax error, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_number_constructors() {
+        let n = Number::from_i64(-5);
+        assert!(n.is_i64() && !n.is_u64() && !n.is_f64());
+        assert_eq!(n.as_i64(), Some(-5));
+        assert_eq!(n.as_u64(), None);
+
+        let n = Number::from_u64(u64::MAX);
+        assert!(!n.is_i64() && n.is_u64() && !n.is_f64());
+        assert_eq!(n.as_u64(), Some(u64::MAX));
+        assert_eq!(to_string(&Value::from(n)).unwrap(), "18446744073709551615");
+
+        let n = Number::from_i64(7);
+        assert!(n.is_i64() && n.is_u64());
+        assert_eq!(n, Number::from_u64(7));
+
+        let n = Number::from_f64(1.5).unwrap();
+        assert!(n.is_f64() && !n.is_i64() && !n.is_u64());
+        assert_eq!(n.as_f64(), 1.5);
+        assert!(Number::from_f64(f64::NAN).is_none());
+        assert!(Number::from_f64(f64::INFINITY).is_none());
+
+        // Integral floats stay floats; integers too large for 64 bits are floats
+        assert!(Number::from_f64(2.0).unwrap().is_f64());
+        assert_eq!(Number::from_f64(2.0).unwrap().as_i64(), None);
+        let big = parse("18446744073709551616").unwrap();
+        assert!(big.as_number().unwrap().is_f64());
+        assert_eq!(big.as_u64(), None);
+    }
+
+    #[test]
+    fn test_value_number_accessors() {
+        let value = parse(r#"[9007199254740993, -1, 2.5, "3"]"#).unwrap();
+        let items = value.as_array().unwrap();
+        // Beyond 2^53, as_f64 would round this to an even number
+        assert_eq!(items[0].as_u64(), Some(9007199254740993));
+        assert_eq!(items[0].as_i64(), Some(9007199254740993));
+        assert_eq!(items[1].as_i64(), Some(-1));
+        assert_eq!(items[1].as_u64(), None);
+        assert_eq!(items[2].as_i64(), None);
+        assert_eq!(items[2].as_number().map(Number::as_f64), Some(2.5));
+        assert_eq!(items[3].as_number(), None);
+        assert_eq!(items[3].as_i64(), None);
+    }
+
+    #[test]
+    fn test_escape_js_separators() {
+        let text = "a\u{2028}b\u{2029}c";
+        // Valid JSON as-is, so left alone by default
+        assert_eq!(to_string(&text).unwrap(), "\"a\u{2028}b\u{2029}c\"");
+
+        let options = SerializeOptions::new().escape_js_separators(true);
+        let json = to_string_with(&text, &options).unwrap();
+        assert_eq!(json, r#""a\u2028b\u2029c""#);
+        assert_eq!(from_str::<String>(&json).unwrap(), text);
+
+        // Other non-ASCII text is untouched
+        assert_eq!(to_string_with(&"é\u{2028}", &options).unwrap(), r#""é\u2028""#);
+    }
+
+    #[test]
+    fn test_serialized_len() {
+        let values = [
+            Value::Null,
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::from(-12),
+            Value::from(u64::MAX),
+            Value::from(0.1),
+            Value::from(1e300),
+            parse("1.000000000000000000001").unwrap(),
+            Value::from(""),
+            Value::from("quote \" backslash \\ newline \n tab \t"),
+            Value::from("\u{0}\u{1f}\u{8}\u{c}\r"),
+            Value::from("café 😀 \u{2028}"),
+            parse("[]").unwrap(),
+            parse("{}").unwrap(),
+            parse(r#"[1, [2, []], {"a": null}]"#).unwrap(),
+            parse(r#"{"k\"ey": {"nested": ["x", "y\n"]}, "é": true}"#).unwrap(),
+        ];
+        for value in &values {
+            assert_eq!(value.serialized_len(), to_string(value).unwrap().len(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_deep_nesting() {
+        // Far deeper than the call stack of a test thread could recurse
+        const DEPTH: usize = 100_000;
+        let options = ParserOptions { max_depth: DEPTH, ..ParserOptions::default() };
+
+        let arrays = format!("{}1{}", "[".repeat(DEPTH), "]".repeat(DEPTH));
+        let objects = format!("{}null{}", r#"{"a": "#.repeat(DEPTH), "}".repeat(DEPTH));
+        for json in [&arrays, &objects] {
+            let mut value = parse_with_options(json, &options).unwrap();
+            let mut depth = 0;
+            // Take the value apart level by level, since dropping it whole would recurse
+            loop {
+                let inner = match &mut value {
+                    Value::Array(items) => items.pop(),
+                    Value::Object(map) => map.remove("a"),
+                    _ => None,
+                };
+                match inner {
+                    Some(inner) => value = inner,
+                    None => break,
+                }
+                depth += 1;
+            }
+            assert_eq!(depth, DEPTH);
+        }
+
+        // One level past the limit is still rejected
+        let too_deep = format!("[{}", arrays);
+        assert!(matches!(
+            parse_with_options(&too_deep, &options),
+            Err(Error::LimitExceeded { limit: "max_depth", .. })
+        ));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,416 @@
+use crate::value::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Objects up to this size are searched linearly instead of through an index
+const INDEX_THRESHOLD: usize = 16;
+
+/// A JSON object: string keys mapped to values, kept in insertion order
+///
+/// Parsing keeps the order keys appear in the document, and derived
+/// `Serialize` impls insert fields in declaration order, so output is
+/// deterministic. Replacing the value of an existing key keeps its position.
+/// Equality ignores order, as for JSON objects.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Value)>,
+    // Key positions, built once the object outgrows a linear scan
+    index: Option<HashMap<String, usize>>,
+}
+
+impl Map {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty map with room for `capacity` entries
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map { entries: Vec::with_capacity(capacity), index: None }
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(key).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index = if self.entries.len() > INDEX_THRESHOLD {
+            Some(self.entries.iter().enumerate().map(|(i, (k, _))| (k.clone(), i)).collect())
+        } else {
+            None
+        };
+    }
+
+    /// The value for `key`, if present
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    /// A mutable reference to the value for `key`, if present
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.position(key).map(move |i| &mut self.entries[i].1)
+    }
+
+    /// Whether the map has an entry for `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Insert an entry, returning the previous value for the key
+    ///
+    /// A new key goes at the end; an existing key keeps its position.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        if let Some(i) = self.position(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        match &mut self.index {
+            Some(index) => {
+                index.insert(key.clone(), self.entries.len());
+            }
+            None if self.entries.len() == INDEX_THRESHOLD => {
+                self.entries.push((key, value));
+                self.rebuild_index();
+                return None;
+            }
+            None => {}
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Remove the entry for `key`, returning its value
+    ///
+    /// The remaining entries keep their order.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let i = self.position(key)?;
+        Some(self.remove_at(i).1)
+    }
+
+    // Remove the entry at position `i`. Later entries shift down, so this is
+    // linear in the map's size, but the index is adjusted in place rather than
+    // rebuilt, which avoids cloning and rehashing every key on each removal.
+    fn remove_at(&mut self, i: usize) -> (String, Value) {
+        let entry = self.entries.remove(i);
+        if self.entries.len() <= INDEX_THRESHOLD {
+            self.index = None;
+        } else if let Some(index) = &mut self.index {
+            index.remove(&entry.0);
+            for position in index.values_mut() {
+                if *position > i {
+                    *position -= 1;
+                }
+            }
+        }
+        entry
+    }
+
+    // Remove every entry, keeping the allocation for reuse
+    pub(crate) fn drain(&mut self) -> std::vec::Drain<'_, (String, Value)> {
+        self.index = None;
+        self.entries.drain(..)
+    }
+
+    /// Keep only the entries for which `f` returns true
+    pub fn retain<F: FnMut(&str, &mut Value) -> bool>(&mut self, mut f: F) {
+        let len = self.entries.len();
+        self.entries.retain_mut(|(k, v)| f(k, v));
+        if self.entries.len() != len {
+            self.rebuild_index();
+        }
+    }
+
+    /// The entry for `key`, for in-place insertion or update
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        let key = key.into();
+        match self.position(&key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    /// Iterate over the entries in order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.entries.iter() }
+    }
+
+    /// Iterate over the entries in order, with mutable values
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut { inner: self.entries.iter_mut() }
+    }
+
+    /// Iterate over the keys in order
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Iterate over the values in order
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// Iterate over the values in order, mutably
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+}
+
+/// A key in a [`Map`], which may or may not have a value yet
+pub enum Entry<'a> {
+    /// The key has no value
+    Vacant(VacantEntry<'a>),
+    /// The key has a value
+    Occupied(OccupiedEntry<'a>),
+}
+
+/// A key with no value in a [`Map`], from [`Map::entry`]
+pub struct VacantEntry<'a> {
+    map: &'a mut Map,
+    key: String,
+}
+
+/// A key with a value in a [`Map`], from [`Map::entry`]
+pub struct OccupiedEntry<'a> {
+    map: &'a mut Map,
+    index: usize,
+}
+
+impl<'a> Entry<'a> {
+    /// The key of this entry
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Vacant(e) => e.key(),
+            Entry::Occupied(e) => e.key(),
+        }
+    }
+
+    /// The value for this key, inserting `default` first if there is none
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        self.or_insert_with(|| default)
+    }
+
+    /// The value for this key, inserting the result of `default` first if there is none
+    pub fn or_insert_with<F: FnOnce() -> Value>(self, default: F) -> &'a mut Value {
+        match self {
+            Entry::Vacant(e) => e.insert(default()),
+            Entry::Occupied(e) => e.into_mut(),
+        }
+    }
+
+    /// The value for this key, inserting `Null` first if there is none
+    pub fn or_default(self) -> &'a mut Value {
+        self.or_insert_with(Value::default)
+    }
+
+    /// Apply `f` to the value if the key has one, leaving a vacant entry as is
+    pub fn and_modify<F: FnOnce(&mut Value)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            vacant => vacant,
+        }
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    /// The key of this entry
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Insert a value at the end of the map, returning a reference to it
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        self.map.insert(self.key, value);
+        let last = self.map.entries.len() - 1;
+        &mut self.map.entries[last].1
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    /// The key of this entry
+    pub fn key(&self) -> &str {
+        &self.map.entries[self.index].0
+    }
+
+    /// The current value
+    pub fn get(&self) -> &Value {
+        &self.map.entries[self.index].1
+    }
+
+    /// A mutable reference to the current value
+    pub fn get_mut(&mut self) -> &mut Value {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Convert into a mutable reference that lives as long as the map borrow
+    pub fn into_mut(self) -> &'a mut Value {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Replace the value in place, returning the old one
+    pub fn insert(&mut self, value: Value) -> Value {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove the entry from the map, returning its value
+    pub fn remove(self) -> Value {
+        self.map.remove_at(self.index).1
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<(String, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, Value)> for Map {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// Unordered maps give their entries in their own iteration order
+impl From<HashMap<String, Value>> for Map {
+    fn from(map: HashMap<String, Value>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl From<Map> for HashMap<String, Value> {
+    fn from(map: Map) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Borrowing iterator over the entries of a [`Map`]
+pub struct Iter<'a> {
+    inner: std::slice::Iter<'a, (String, Value)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Mutably borrowing iterator over the entries of a [`Map`]
+pub struct IterMut<'a> {
+    inner: std::slice::IterMut<'a, (String, Value)>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+/// Owning iterator over the entries of a [`Map`]
+pub struct IntoIter {
+    inner: std::vec::IntoIter<(String, Value)>,
+}
+
+impl Iterator for IntoIter {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for Map {
+    type Item = (String, Value);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { inner: self.entries.into_iter() }
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
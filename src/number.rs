@@ -0,0 +1,172 @@
+use crate::value::format_number;
+use std::fmt;
+
+/// A JSON number
+///
+/// Integers parsed from JSON text or converted from Rust integer types are
+/// kept exactly; numbers with a fraction or exponent are stored as `f64`.
+/// Parsed numbers that are not exact integers also keep their source text,
+/// which is written back out verbatim, so `0.10` stays `0.10`.
+/// Equality is numeric, so `1` and `1.0` compare equal.
+#[derive(Clone)]
+pub struct Number {
+    n: N,
+}
+
+#[derive(Clone)]
+enum N {
+    PosInt(u64),
+    /// Always less than zero
+    NegInt(i64),
+    Float(f64),
+    /// A float or out-of-range integer parsed from JSON, with its literal text
+    Parsed(f64, Box<str>),
+}
+
+impl Number {
+    /// A number holding `n` exactly
+    pub fn from_i64(n: i64) -> Self {
+        Number::from(n)
+    }
+
+    /// A number holding `n` exactly
+    pub fn from_u64(n: u64) -> Self {
+        Number::from(n)
+    }
+
+    /// A number holding `n`, or `None` if it is NaN or infinite, which JSON cannot represent
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if n.is_finite() {
+            Some(Number { n: N::Float(n) })
+        } else {
+            None
+        }
+    }
+
+    /// Whether this number is an integer that fits in an `i64`
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Whether this number is an integer that fits in a `u64`
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Whether this number is stored as a float
+    ///
+    /// True for numbers with a fraction or exponent, like `1.0` or `1e3`, and
+    /// for integers too large for `i64` and `u64`.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_) | N::Parsed(..))
+    }
+
+    /// The value as an `f64`, rounding integers beyond 2^53 to the nearest float
+    pub fn as_f64(&self) -> f64 {
+        match self.n {
+            N::PosInt(n) => n as f64,
+            N::NegInt(n) => n as f64,
+            N::Float(n) | N::Parsed(n, _) => n,
+        }
+    }
+
+    /// The exact value if this number is an integer that fits in an `i64`
+    ///
+    /// Returns `None` for floats, even integral ones like `1.0`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            N::Float(_) | N::Parsed(..) => None,
+        }
+    }
+
+    /// The exact value if this number is an integer that fits in a `u64`
+    ///
+    /// Returns `None` for floats, even integral ones like `1.0`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(n) => Some(n),
+            N::NegInt(_) | N::Float(_) | N::Parsed(..) => None,
+        }
+    }
+
+    // The exact integer value, for range checks across every integer type
+    pub(crate) fn as_integer(&self) -> Option<i128> {
+        match self.n {
+            N::PosInt(n) => Some(n as i128),
+            N::NegInt(n) => Some(n as i128),
+            N::Float(_) | N::Parsed(..) => None,
+        }
+    }
+
+    // A number parsed from `text`, which the parser has already converted to `n`
+    pub(crate) fn from_parsed(n: f64, text: &str) -> Self {
+        Number { n: N::Parsed(n, text.into()) }
+    }
+
+    // The literal this number was parsed from, when it is not an exact integer
+    pub(crate) fn source_text(&self) -> Option<&str> {
+        match &self.n {
+            N::Parsed(_, text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        match (self.as_integer(), other.as_integer()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.as_f64() == other.as_f64(),
+        }
+    }
+}
+
+impl From<u64> for Number {
+    fn from(n: u64) -> Self {
+        Number { n: N::PosInt(n) }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Self {
+        if n < 0 {
+            Number { n: N::NegInt(n) }
+        } else {
+            Number { n: N::PosInt(n as u64) }
+        }
+    }
+}
+
+macro_rules! from_integer_impls {
+    ($($ty:ty => $via:ty)+) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(n: $ty) -> Self {
+                    Number::from(n as $via)
+                }
+            }
+        )+
+    };
+}
+
+from_integer_impls!(i8 => i64 i16 => i64 i32 => i64 u8 => u64 u16 => u64 u32 => u64);
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.n {
+            N::PosInt(n) => write!(f, "{}", n),
+            N::NegInt(n) => write!(f, "{}", n),
+            N::Float(n) => f.write_str(&format_number(n)),
+            N::Parsed(_, ref text) => f.write_str(text),
+        }
+    }
+}
+
+// Prints like the number itself so Value's Debug output stays readable
+impl fmt::Debug for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
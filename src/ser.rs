@@ -1,11 +1,252 @@
 use crate::error::{Error, Result};
-use crate::value::Value;
-use std::collections::HashMap;
+use crate::map::Map;
+use crate::number::Number;
+use crate::value::{format_number, write_close, write_number, write_separator, write_string, Value};
+use std::collections::{HashMap, LinkedList, VecDeque};
+use std::fmt::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use std::num::Wrapping;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// A trait for types that can be serialized to JSON
 pub trait Serialize {
     /// Serialize this value into JSON
     fn serialize(&self) -> Result<Value>;
+
+    /// Drive a [`Serializer`] with this value's structure
+    ///
+    /// The default builds the `Value` with [`Serialize::serialize`] and walks it,
+    /// so every implementation works with custom serializers. Implementations
+    /// can override this to emit events directly without building a `Value`.
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        self.serialize()?.serialize_to(serializer)
+    }
+}
+
+/// A sink for the structure of a serialized value, for custom output formats
+///
+/// Values are emitted as a stream of events. Sequences are bracketed by
+/// `serialize_seq`/`end_seq`; maps by `serialize_map`/`end_map`, with each
+/// entry emitted as `serialize_key` followed by exactly one value.
+pub trait Serializer {
+    /// Emit a null
+    fn serialize_null(&mut self) -> Result<()>;
+    /// Emit a boolean
+    fn serialize_bool(&mut self, v: bool) -> Result<()>;
+    /// Emit a floating point number
+    fn serialize_f64(&mut self, v: f64) -> Result<()>;
+    /// Emit an exact signed integer; defaults to [`Serializer::serialize_f64`]
+    fn serialize_i64(&mut self, v: i64) -> Result<()> {
+        self.serialize_f64(v as f64)
+    }
+    /// Emit an exact unsigned integer; defaults to [`Serializer::serialize_f64`]
+    fn serialize_u64(&mut self, v: u64) -> Result<()> {
+        self.serialize_f64(v as f64)
+    }
+    /// Emit a [`Number`] held by a [`Value`]
+    ///
+    /// Defaults to the exact integer methods where the number is an integer
+    /// and [`Serializer::serialize_f64`] otherwise. Override it to see the
+    /// literal text of parsed numbers, which [`Number`]'s `Display` writes.
+    fn serialize_number(&mut self, v: &Number) -> Result<()> {
+        match (v.as_u64(), v.as_i64()) {
+            (Some(u), _) => self.serialize_u64(u),
+            (None, Some(i)) => self.serialize_i64(i),
+            (None, None) => self.serialize_f64(v.as_f64()),
+        }
+    }
+    /// Emit a string
+    fn serialize_str(&mut self, v: &str) -> Result<()>;
+    /// Begin a sequence of `len` elements
+    fn serialize_seq(&mut self, len: usize) -> Result<()>;
+    /// End the innermost sequence
+    fn end_seq(&mut self) -> Result<()>;
+    /// Begin a map of `len` entries
+    fn serialize_map(&mut self, len: usize) -> Result<()>;
+    /// Emit the key of the next map entry; its value follows
+    fn serialize_key(&mut self, key: &str) -> Result<()>;
+    /// End the innermost map
+    fn end_map(&mut self) -> Result<()>;
+}
+
+/// The reference [`Serializer`], writing JSON text
+///
+/// [`JsonSerializer::new`] formats the output the same way as [`to_string`];
+/// [`JsonSerializer::with_options`] follows a [`SerializeOptions`] the way
+/// [`to_string_with`] does.
+#[derive(Debug, Default)]
+pub struct JsonSerializer {
+    output: String,
+    options: SerializeOptions,
+    // One entry per open sequence or map
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+struct Frame {
+    is_map: bool,
+    // Elements or entries written so far
+    len: usize,
+    // Under sort_keys, each entry's key and where its text starts in the
+    // output, so the entries can be reordered once the map ends
+    entries: Vec<(String, usize)>,
+}
+
+impl JsonSerializer {
+    /// Create a serializer with empty output
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a serializer with empty output, formatted according to `options`
+    pub fn with_options(options: SerializeOptions) -> Self {
+        JsonSerializer { options, ..Self::default() }
+    }
+
+    /// Consume the serializer, returning the JSON text written so far
+    ///
+    /// A newline is appended if the options ask for a trailing newline.
+    pub fn into_string(mut self) -> String {
+        if self.options.trailing_newline {
+            self.output.push('\n');
+        }
+        self.output
+    }
+
+    // Write the separator before a sequence element; map values follow their key
+    fn begin_value(&mut self) {
+        let depth = self.frames.len();
+        if let Some(frame) = self.frames.last_mut() {
+            if !frame.is_map {
+                write_separator(&mut self.output, &self.options, frame.len == 0, depth);
+                frame.len += 1;
+            }
+        }
+    }
+
+    fn open(&mut self, bracket: char, is_map: bool) {
+        self.begin_value();
+        self.output.push(bracket);
+        self.frames.push(Frame { is_map, len: 0, entries: Vec::new() });
+    }
+
+    fn close(&mut self, bracket: char) {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        if frame.len == 0 {
+            self.output.push(bracket);
+            return;
+        }
+        let depth = self.frames.len();
+        if frame.is_map && self.options.sort_keys {
+            // Entries were written without separators; put them in key order
+            let start = frame.entries[0].1;
+            let text = self.output.split_off(start);
+            let mut entries: Vec<(&str, &str)> = Vec::with_capacity(frame.entries.len());
+            for (i, (key, from)) in frame.entries.iter().enumerate() {
+                let to = frame.entries.get(i + 1).map_or(text.len(), |(_, next)| next - start);
+                entries.push((key, &text[from - start..to]));
+            }
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (i, (_, entry)) in entries.into_iter().enumerate() {
+                write_separator(&mut self.output, &self.options, i == 0, depth + 1);
+                self.output.push_str(entry);
+            }
+        }
+        write_close(&mut self.output, &self.options, depth, bracket);
+    }
+}
+
+impl Serializer for JsonSerializer {
+    fn serialize_null(&mut self) -> Result<()> {
+        self.begin_value();
+        self.output.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_bool(&mut self, v: bool) -> Result<()> {
+        self.begin_value();
+        self.output.push_str(if v { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_f64(&mut self, v: f64) -> Result<()> {
+        self.begin_value();
+        match self.options.float_precision {
+            Some(digits) => {
+                let _ = write!(self.output, "{:.*}", digits, v);
+            }
+            None => self.output.push_str(&format_number(v)),
+        }
+        Ok(())
+    }
+
+    // Integers are formatted straight into the output, so large numeric
+    // arrays need no allocation per element
+    fn serialize_i64(&mut self, v: i64) -> Result<()> {
+        self.begin_value();
+        let _ = write!(self.output, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_u64(&mut self, v: u64) -> Result<()> {
+        self.begin_value();
+        let _ = write!(self.output, "{}", v);
+        Ok(())
+    }
+
+    fn serialize_number(&mut self, v: &Number) -> Result<()> {
+        self.begin_value();
+        write_number(&mut self.output, v, &self.options);
+        Ok(())
+    }
+
+    fn serialize_str(&mut self, v: &str) -> Result<()> {
+        self.begin_value();
+        write_string(&mut self.output, v, &self.options);
+        Ok(())
+    }
+
+    fn serialize_seq(&mut self, _len: usize) -> Result<()> {
+        self.open('[', false);
+        Ok(())
+    }
+
+    fn end_seq(&mut self) -> Result<()> {
+        self.close(']');
+        Ok(())
+    }
+
+    fn serialize_map(&mut self, _len: usize) -> Result<()> {
+        self.open('{', true);
+        Ok(())
+    }
+
+    fn serialize_key(&mut self, key: &str) -> Result<()> {
+        let depth = self.frames.len();
+        if let Some(frame) = self.frames.last_mut() {
+            if self.options.sort_keys {
+                frame.entries.push((key.to_owned(), self.output.len()));
+            } else {
+                write_separator(&mut self.output, &self.options, frame.len == 0, depth);
+            }
+            frame.len += 1;
+        }
+        write_string(&mut self.output, key, &self.options);
+        self.output.push_str(": ");
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> Result<()> {
+        self.close('}');
+        Ok(())
+    }
 }
 
 impl Serialize for bool {
@@ -16,68 +257,80 @@ impl Serialize for bool {
 
 impl Serialize for i8 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for i16 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for i32 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for i64 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for u8 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
+    }
+
+    // Byte buffers are often large; skip building a Value per byte
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_u64(u64::from(*self))
     }
 }
 
 impl Serialize for u16 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for u32 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
     }
 }
 
 impl Serialize for u64 {
     fn serialize(&self) -> Result<Value> {
-        // JSON doesn't support 64-bit integers precisely, so check for overflow
-        if *self > 9007199254740991 { // 2^53 - 1, largest integer precisely representable in f64
-            return Err(Error::custom(format!("integer too large for JSON: {}", self)));
-        }
-        Ok(Value::Number(*self as f64))
+        Ok(Value::Number(Number::from(*self)))
+    }
+}
+
+impl Serialize for isize {
+    fn serialize(&self) -> Result<Value> {
+        (*self as i64).serialize()
+    }
+}
+
+impl Serialize for usize {
+    fn serialize(&self) -> Result<Value> {
+        (*self as u64).serialize()
     }
 }
 
 impl Serialize for f32 {
     fn serialize(&self) -> Result<Value> {
-        Ok(Value::Number(*self as f64))
+        f64::from(*self).serialize()
     }
 }
 
 impl Serialize for f64 {
     fn serialize(&self) -> Result<Value> {
-        if self.is_finite() {
-            Ok(Value::Number(*self))
-        } else {
-            Err(Error::custom(format!("non-finite number cannot be serialized: {}", self)))
+        match Number::from_f64(*self) {
+            Some(n) => Ok(Value::Number(n)),
+            None => Err(Error::custom(format!("non-finite number cannot be serialized: {}", self))),
         }
     }
 }
@@ -86,12 +339,20 @@ impl Serialize for str {
     fn serialize(&self) -> Result<Value> {
         Ok(Value::String(self.to_owned()))
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_str(self)
+    }
 }
 
 impl Serialize for String {
     fn serialize(&self) -> Result<Value> {
         Ok(Value::String(self.clone()))
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_str(self)
+    }
 }
 
 impl<T: Serialize> Serialize for Option<T> {
@@ -101,6 +362,72 @@ impl<T: Serialize> Serialize for Option<T> {
             None => Ok(Value::Null),
         }
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        match self {
+            Some(value) => value.serialize_to(serializer),
+            None => serializer.serialize_null(),
+        }
+    }
+}
+
+// Wrapping integers are written as the plain inner value
+impl<T: Serialize> Serialize for Wrapping<T> {
+    fn serialize(&self) -> Result<Value> {
+        self.0.serialize()
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        self.0.serialize_to(serializer)
+    }
+}
+
+// Externally tagged like serde: {"Ok": value} or {"Err": error}
+impl<T: Serialize, E: Serialize> Serialize for std::result::Result<T, E> {
+    fn serialize(&self) -> Result<Value> {
+        let (tag, content) = match self {
+            Ok(value) => ("Ok", value.serialize()?),
+            Err(error) => ("Err", error.serialize()?),
+        };
+        let mut map = Map::with_capacity(1);
+        map.insert(tag.to_string(), content);
+        Ok(Value::Object(map))
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_map(1)?;
+        match self {
+            Ok(value) => {
+                serializer.serialize_key("Ok")?;
+                value.serialize_to(serializer)?;
+            }
+            Err(error) => {
+                serializer.serialize_key("Err")?;
+                error.serialize_to(serializer)?;
+            }
+        }
+        serializer.end_map()
+    }
+}
+
+// Ranges use serde's shape: {"start": a, "end": b}
+fn serialize_range<T: Serialize>(start: &T, end: &T) -> Result<Value> {
+    let mut map = Map::with_capacity(2);
+    map.insert("start".to_string(), start.serialize()?);
+    map.insert("end".to_string(), end.serialize()?);
+    Ok(Value::Object(map))
+}
+
+impl<T: Serialize> Serialize for Range<T> {
+    fn serialize(&self) -> Result<Value> {
+        serialize_range(&self.start, &self.end)
+    }
+}
+
+impl<T: Serialize> Serialize for RangeInclusive<T> {
+    fn serialize(&self) -> Result<Value> {
+        serialize_range(self.start(), self.end())
+    }
 }
 
 impl<T: Serialize> Serialize for [T] {
@@ -111,27 +438,175 @@ impl<T: Serialize> Serialize for [T] {
         }
         Ok(Value::Array(vec))
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_seq(self.len())?;
+        for item in self {
+            item.serialize_to(serializer)?;
+        }
+        serializer.end_seq()
+    }
 }
 
 impl<T: Serialize> Serialize for Vec<T> {
     fn serialize(&self) -> Result<Value> {
         <[T] as Serialize>::serialize(self)
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        <[T] as Serialize>::serialize_to(self, serializer)
+    }
+}
+
+// Other sequence collections serialize as arrays, in iteration order
+macro_rules! seq_impls {
+    ($($ty:ident)+) => {
+        $(
+            impl<T: Serialize> Serialize for $ty<T> {
+                fn serialize(&self) -> Result<Value> {
+                    let mut vec = Vec::with_capacity(self.len());
+                    for item in self {
+                        vec.push(item.serialize()?);
+                    }
+                    Ok(Value::Array(vec))
+                }
+
+                fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+                    serializer.serialize_seq(self.len())?;
+                    for item in self {
+                        item.serialize_to(serializer)?;
+                    }
+                    serializer.end_seq()
+                }
+            }
+        )+
+    };
+}
+
+seq_impls!(VecDeque LinkedList);
+
+// Tuples serialize as fixed-length arrays
+macro_rules! tuple_impls {
+    ($(($($n:tt $name:ident)+))+) => {
+        $(
+            impl<$($name: Serialize),+> Serialize for ($($name,)+) {
+                fn serialize(&self) -> Result<Value> {
+                    Ok(Value::Array(vec![$(self.$n.serialize()?),+]))
+                }
+            }
+        )+
+    };
+}
+
+tuple_impls! {
+    (0 A)
+    (0 A 1 B)
+    (0 A 1 B 2 C)
+    (0 A 1 B 2 C 3 D)
+    (0 A 1 B 2 C 3 D 4 E)
+    (0 A 1 B 2 C 3 D 4 E 5 F)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H 8 I)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H 8 I 9 J)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H 8 I 9 J 10 K)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H 8 I 9 J 10 K 11 L)
 }
 
-impl<K: AsRef<str>, V: Serialize> Serialize for HashMap<K, V> {
+// Keys are written in their Display form, so integer keys such as
+// `HashMap<u32, _>` become strings like "42"; deserializing parses them back
+// through FromStr
+impl<K: fmt::Display, V: Serialize> Serialize for HashMap<K, V> {
     fn serialize(&self) -> Result<Value> {
-        let mut map = HashMap::with_capacity(self.len());
+        let mut map = Map::with_capacity(self.len());
         for (key, value) in self {
-            map.insert(key.as_ref().to_owned(), value.serialize()?);
+            map.insert(key.to_string(), value.serialize()?);
         }
         Ok(Value::Object(map))
     }
 }
 
-impl<T: Serialize> Serialize for &T {
+// Encoded as {"secs": N, "nanos": M}, the same shape serde uses
+impl Serialize for Duration {
+    fn serialize(&self) -> Result<Value> {
+        let mut map = Map::with_capacity(2);
+        map.insert("secs".to_owned(), self.as_secs().serialize()?);
+        map.insert("nanos".to_owned(), self.subsec_nanos().serialize()?);
+        Ok(Value::Object(map))
+    }
+}
+
+// Paths must be valid UTF-8 to be represented as a JSON string
+impl Serialize for Path {
+    fn serialize(&self) -> Result<Value> {
+        match self.to_str() {
+            Some(s) => Ok(Value::String(s.to_owned())),
+            None => Err(Error::custom(format!("path contains invalid UTF-8: {}", self.display()))),
+        }
+    }
+}
+
+impl Serialize for PathBuf {
+    fn serialize(&self) -> Result<Value> {
+        self.as_path().serialize()
+    }
+}
+
+// Types without a native JSON representation are encoded as their Display string form
+macro_rules! display_impls {
+    ($($ty:ty)+) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize(&self) -> Result<Value> {
+                    Ok(Value::String(self.to_string()))
+                }
+            }
+        )+
+    };
+}
+
+display_impls!(IpAddr Ipv4Addr Ipv6Addr SocketAddr SocketAddrV4 SocketAddrV6);
+
+// 128-bit integers do not fit in an f64, so they travel as strings
+display_impls!(i128 u128);
+
+impl<T: Serialize + ?Sized> Serialize for &T {
+    fn serialize(&self) -> Result<Value> {
+        (**self).serialize()
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        (**self).serialize_to(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for Box<T> {
+    fn serialize(&self) -> Result<Value> {
+        (**self).serialize()
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        (**self).serialize_to(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for Rc<T> {
+    fn serialize(&self) -> Result<Value> {
+        (**self).serialize()
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        (**self).serialize_to(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for Arc<T> {
     fn serialize(&self) -> Result<Value> {
-        (*self).serialize()
+        (**self).serialize()
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        (**self).serialize_to(serializer)
     }
 }
 
@@ -139,90 +614,203 @@ impl Serialize for Value {
     fn serialize(&self) -> Result<Value> {
         Ok(self.clone())
     }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        match self {
+            Value::Null => serializer.serialize_null(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => serializer.serialize_number(n),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => {
+                serializer.serialize_seq(a.len())?;
+                for item in a {
+                    item.serialize_to(serializer)?;
+                }
+                serializer.end_seq()
+            }
+            Value::Object(o) => o.serialize_to(serializer),
+        }
+    }
+}
+
+impl Serialize for Map {
+    fn serialize(&self) -> Result<Value> {
+        Ok(Value::Object(self.clone()))
+    }
+
+    fn serialize_to(&self, serializer: &mut dyn Serializer) -> Result<()> {
+        serializer.serialize_map(self.len())?;
+        for (key, value) in self {
+            serializer.serialize_key(key)?;
+            value.serialize_to(serializer)?;
+        }
+        serializer.end_map()
+    }
+}
+
+// Serializes the items of an iterator into a Value::Array without
+// collecting them into a Vec first
+pub fn serialize_iter<I, T>(iter: I) -> Result<Value>
+where
+    I: IntoIterator<Item = T>,
+    T: Serialize,
+{
+    let iter = iter.into_iter();
+    let mut items = Vec::with_capacity(iter.size_hint().0);
+    for item in iter {
+        items.push(item.serialize()?);
+    }
+    Ok(Value::Array(items))
 }
 
 // Serializes any value to a JSON string
+//
+// Compact output is streamed through a JsonSerializer rather than built as a
+// Value first, so collections such as a large Vec<u8> are written element by
+// element without an intermediate Vec<Value>
 pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
-    let value = value.serialize()?;
-    Ok(value.to_string())
+    let mut serializer = JsonSerializer::new();
+    value.serialize_to(&mut serializer)?;
+    Ok(serializer.into_string())
 }
 
 // Serializes any value to a pretty-printed JSON string with indentation
 pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String> {
-    let value = value.serialize()?;
-    pretty_print(&value, 0)
-}
-
-fn pretty_print(value: &Value, indent: usize) -> Result<String> {
-    match value {
-        Value::Null => Ok("null".to_owned()),
-        Value::Bool(b) => Ok(b.to_string()),
-        Value::Number(n) => Ok(n.to_string()),
-        Value::String(s) => {
-            let escaped = s.chars()
-                .map(|c| match c {
-                    '"' => "\\\"".to_owned(),
-                    '\\' => "\\\\".to_owned(),
-                    '\n' => "\\n".to_owned(),
-                    '\r' => "\\r".to_owned(),
-                    '\t' => "\\t".to_owned(),
-                    '\u{0008}' => "\\b".to_owned(),
-                    '\u{000C}' => "\\f".to_owned(),
-                    _ => c.to_string(),
-                })
-                .collect::<Vec<_>>()
-                .join("");
-            Ok(format!("\"{}\"", escaped))
-        },
-        Value::Array(a) => {
-            if a.is_empty() {
-                return Ok("[]".to_owned());
-            }
-            
-            let next_indent = indent + 2;
-            let mut result = String::from("[\n");
-            
-            for (i, item) in a.iter().enumerate() {
-                result.push_str(&" ".repeat(next_indent));
-                result.push_str(&pretty_print(item, next_indent)?);
-                
-                if i < a.len() - 1 {
-                    result.push_str(",\n");
-                } else {
-                    result.push('\n');
-                }
-            }
-            
-            result.push_str(&" ".repeat(indent));
-            result.push(']');
-            Ok(result)
-        },
-        Value::Object(o) => {
-            if o.is_empty() {
-                return Ok("{}".to_owned());
-            }
-            
-            let next_indent = indent + 2;
-            let mut result = String::from("{\n");
-            
-            let len = o.len();
-            for (i, (key, value)) in o.iter().enumerate() {
-                result.push_str(&" ".repeat(next_indent));
-                result.push('"');
-                result.push_str(key);
-                result.push_str("\": ");
-                result.push_str(&pretty_print(value, next_indent)?);
-                
-                if i < len - 1 {
-                    result.push_str(",\n");
-                } else {
-                    result.push('\n');
-                }
-            }
-            
-            result.push_str(&" ".repeat(indent));
-            result.push('}');
-            Ok(result)
-        }
+    to_string_with(value, &SerializeOptions::new().pretty())
+}
+
+// Serializes any value to UTF-8 encoded JSON bytes
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    to_string(value).map(String::into_bytes)
+}
+
+// Serializes any value to UTF-8 encoded, pretty-printed JSON bytes
+pub fn to_vec_pretty<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    to_string_pretty(value).map(String::into_bytes)
+}
+
+/// Marker for types whose serialization cannot fail, see [`to_string_infallible`]
+///
+/// Implemented for booleans, integers, strings, [`Value`] and [`Map`], and for
+/// options, sequences, string-keyed maps and smart pointers of those. Floats
+/// are left out because NaN and infinity are rejected, as are paths, which
+/// may not be valid UTF-8.
+pub trait InfallibleSerialize: Serialize {}
+
+macro_rules! infallible_impls {
+    ($($ty:ty)+) => {
+        $(impl InfallibleSerialize for $ty {})+
+    };
+}
+
+infallible_impls!(bool i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize str String Value Map);
+
+impl<T: InfallibleSerialize> InfallibleSerialize for Option<T> {}
+impl<T: InfallibleSerialize> InfallibleSerialize for Wrapping<T> {}
+impl<T: InfallibleSerialize> InfallibleSerialize for [T] {}
+impl<T: InfallibleSerialize> InfallibleSerialize for Vec<T> {}
+impl<T: InfallibleSerialize> InfallibleSerialize for VecDeque<T> {}
+impl<K: fmt::Display, V: InfallibleSerialize> InfallibleSerialize for HashMap<K, V> {}
+impl<T: InfallibleSerialize + ?Sized> InfallibleSerialize for &T {}
+impl<T: InfallibleSerialize + ?Sized> InfallibleSerialize for Box<T> {}
+impl<T: InfallibleSerialize + ?Sized> InfallibleSerialize for Rc<T> {}
+impl<T: InfallibleSerialize + ?Sized> InfallibleSerialize for Arc<T> {}
+
+// Serializes a value that cannot fail to a JSON string, without a Result
+pub fn to_string_infallible<T: InfallibleSerialize + ?Sized>(value: &T) -> String {
+    to_string(value).expect("InfallibleSerialize types always serialize")
+}
+
+/// Options controlling JSON text output, used with [`to_string_with`]
+///
+/// Built by chaining methods onto [`SerializeOptions::new`], which produces the
+/// same compact output as [`to_string`]:
+///
+/// ```
+/// use fastjson::{to_string_with, SerializeOptions};
+///
+/// let options = SerializeOptions::new().indent(4).sort_keys(true).escape_non_ascii(true);
+/// let json = to_string_with(&vec!["café"], &options).unwrap();
+/// assert_eq!(json, "[\n    \"caf\\u00e9\"\n]");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    pub(crate) indent: Option<usize>,
+    pub(crate) sort_keys: bool,
+    pub(crate) escape_non_ascii: bool,
+    pub(crate) escape_forward_slash: bool,
+    pub(crate) escape_js_separators: bool,
+    pub(crate) trailing_newline: bool,
+    pub(crate) float_precision: Option<usize>,
+}
+
+impl SerializeOptions {
+    /// Compact output, matching [`to_string`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pretty-print with two-space indentation, matching [`to_string_pretty`]
+    pub fn pretty(self) -> Self {
+        self.indent(2)
+    }
+
+    /// Pretty-print, indenting each nesting level by `spaces` spaces
+    pub fn indent(mut self, spaces: usize) -> Self {
+        self.indent = Some(spaces);
+        self
+    }
+
+    /// Write object keys in sorted order instead of map order
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Write every non-ASCII character as a `\uXXXX` escape, so the output is pure ASCII
+    pub fn escape_non_ascii(mut self, escape_non_ascii: bool) -> Self {
+        self.escape_non_ascii = escape_non_ascii;
+        self
+    }
+
+    /// Write `/` as `\/`, for sinks such as inline HTML `<script>` blocks that
+    /// must never see `</`
+    pub fn escape_forward_slash(mut self, escape_forward_slash: bool) -> Self {
+        self.escape_forward_slash = escape_forward_slash;
+        self
+    }
+
+    /// Write U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR as `\u2028`
+    /// and `\u2029`
+    ///
+    /// Both are valid in JSON strings but end the line in older JavaScript
+    /// parsers, so JSON embedded in a `<script>` block needs them escaped.
+    pub fn escape_js_separators(mut self, escape_js_separators: bool) -> Self {
+        self.escape_js_separators = escape_js_separators;
+        self
     }
-}
\ No newline at end of file
+
+    /// End the output with a newline, as many editors and linters expect of files
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Write floats with exactly `digits` decimal places, e.g. `1.50` for 1.5
+    /// with 2 digits
+    ///
+    /// Integers are still written exactly, without a fraction. By default floats
+    /// use the shortest digits that parse back to the same value.
+    pub fn float_precision(mut self, digits: usize) -> Self {
+        self.float_precision = Some(digits);
+        self
+    }
+}
+
+// Serializes any value to a JSON string using the given options, streamed
+// through a JsonSerializer like to_string
+pub fn to_string_with<T: Serialize + ?Sized>(value: &T, options: &SerializeOptions) -> Result<String> {
+    let mut serializer = JsonSerializer::with_options(options.clone());
+    value.serialize_to(&mut serializer)?;
+    Ok(serializer.into_string())
+}
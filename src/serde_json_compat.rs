@@ -0,0 +1,77 @@
+//! Conversions to and from `serde_json::Value`, behind the `serde_json` feature
+//!
+//! Integers convert exactly in both directions. Floats convert through `f64`,
+//! so a parsed number's source text (see `Value::as_number_str`) is not carried
+//! over. Object keys keep whatever order the source map iterates in.
+
+use crate::map::Map;
+use crate::number::Number;
+use crate::value::Value;
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            // Only numbers beyond f64 under serde_json's arbitrary_precision fail
+            serde_json::Value::Number(n) => Number::try_from(n).map(Value::Number).unwrap_or(Value::Null),
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(items) => Value::Array(items.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(map) => Value::Object(Map::from(map)),
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Number(n) => serde_json::Value::Number(n.into()),
+            Value::String(s) => serde_json::Value::String(s),
+            Value::Array(items) => serde_json::Value::Array(items.into_iter().map(serde_json::Value::from).collect()),
+            Value::Object(map) => serde_json::Value::Object(map.into()),
+        }
+    }
+}
+
+impl TryFrom<serde_json::Number> for Number {
+    type Error = serde_json::Number;
+
+    /// Fails only for numbers no `f64` can hold, which serde_json accepts
+    /// under its `arbitrary_precision` feature
+    fn try_from(n: serde_json::Number) -> Result<Self, serde_json::Number> {
+        if let Some(u) = n.as_u64() {
+            Ok(Number::from_u64(u))
+        } else if let Some(i) = n.as_i64() {
+            Ok(Number::from_i64(i))
+        } else {
+            n.as_f64().and_then(Number::from_f64).ok_or(n)
+        }
+    }
+}
+
+impl From<Number> for serde_json::Number {
+    fn from(n: Number) -> Self {
+        if let Some(u) = n.as_u64() {
+            u.into()
+        } else if let Some(i) = n.as_i64() {
+            i.into()
+        } else {
+            // A Number is always finite, which is all serde_json requires of a float
+            serde_json::Number::from_f64(n.as_f64()).expect("Number holds a finite f64")
+        }
+    }
+}
+
+impl From<serde_json::Map<String, serde_json::Value>> for Map {
+    fn from(map: serde_json::Map<String, serde_json::Value>) -> Self {
+        map.into_iter().map(|(k, v)| (k, Value::from(v))).collect()
+    }
+}
+
+impl From<Map> for serde_json::Map<String, serde_json::Value> {
+    fn from(map: Map) -> Self {
+        map.into_iter().map(|(k, v)| (k, serde_json::Value::from(v))).collect()
+    }
+}
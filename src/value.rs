@@ -1,24 +1,43 @@
+use crate::de::Deserialize;
+use crate::error::{Error, Result};
+use crate::map::{Entry, Map};
+use crate::number::Number;
+use crate::ser::SerializeOptions;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fmt;
+use std::fmt::{self, Write};
 
 /// Represents any valid JSON value
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum Value {
     /// JSON null
+    #[default]
     Null,
     /// JSON boolean
     Bool(bool),
-    /// JSON number (stored as f64 for simplicity)
-    Number(f64),
+    /// JSON number, with integers kept exact
+    Number(Number),
     /// JSON string
     String(String),
     /// JSON array
     Array(Vec<Value>),
-    /// JSON object
-    Object(HashMap<String, Value>),
+    /// JSON object, keeping its keys in insertion order
+    Object(Map),
 }
 
 impl Value {
+    /// A short name for the kind of value, for use in diagnostics
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
     /// Returns true if the value is null
     pub fn is_null(&self) -> bool {
         matches!(self, Value::Null)
@@ -60,11 +79,29 @@ impl Value {
     /// Try to get this value as a number
     pub fn as_f64(&self) -> Option<f64> {
         match self {
-            Value::Number(n) => Some(*n),
+            Value::Number(n) => Some(n.as_f64()),
             _ => None,
         }
     }
 
+    /// Try to get this value as a `Number`, keeping integers exact
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Try to get this value as an integer that fits in an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    /// Try to get this value as an integer that fits in a `u64`
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
     /// Try to get this value as a string reference
     pub fn as_str(&self) -> Option<&str> {
         match self {
@@ -73,6 +110,34 @@ impl Value {
         }
     }
 
+    /// The exact text of a number, or `None` for other values
+    ///
+    /// Parsed numbers give back their literal as written in the document,
+    /// without rounding through `f64`, so `0.10` and `1.000000000000000000001`
+    /// come back unchanged. Exact integers, and numbers built from Rust values,
+    /// give the text serialization writes for them.
+    pub fn as_number_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Value::Number(n) => Some(match n.source_text() {
+                Some(text) => Cow::Borrowed(text),
+                None => Cow::Owned(n.to_string()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Render a scalar as text, or `None` for arrays and objects
+    ///
+    /// Strings are returned as-is, without quotes or escaping. Numbers, bools
+    /// and null are written exactly as serialization writes them.
+    pub fn as_scalar_string(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::Null | Value::Bool(_) | Value::Number(_) => Some(self.to_string()),
+            Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+
     /// Try to get this value as an array reference
     pub fn as_array(&self) -> Option<&[Value]> {
         match self {
@@ -90,7 +155,7 @@ impl Value {
     }
 
     /// Try to get this value as an object reference
-    pub fn as_object(&self) -> Option<&HashMap<String, Value>> {
+    pub fn as_object(&self) -> Option<&Map> {
         match self {
             Value::Object(o) => Some(o),
             _ => None,
@@ -98,17 +163,387 @@ impl Value {
     }
 
     /// Try to get this value as a mutable object reference
-    pub fn as_object_mut(&mut self) -> Option<&mut HashMap<String, Value>> {
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
         match self {
             Value::Object(o) => Some(o),
             _ => None,
         }
     }
 
+    /// Number of elements in an array, entries in an object, or bytes in a string
+    ///
+    /// Returns `None` for null, booleans and numbers.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Value::String(s) => Some(s.len()),
+            Value::Array(a) => Some(a.len()),
+            Value::Object(o) => Some(o.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns true for an empty array, object, or string
+    ///
+    /// Scalars (including null) are never considered empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Insert a key into an object, returning the previous value for that key
+    ///
+    /// `Null` is promoted to an empty object first, so objects can be built up
+    /// from `Value::default()`. Any other non-object value is left untouched
+    /// and an error is returned.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) -> Result<Option<Value>> {
+        if self.is_null() {
+            *self = Value::Object(Map::new());
+        }
+        match self {
+            Value::Object(o) => Ok(o.insert(key.into(), value.into())),
+            _ => Err(Error::TypeError(format!("cannot insert a key into {}", self.type_name()))),
+        }
+    }
+
+    /// The entry for `key` in an object, for in-place insertion or update
+    ///
+    /// Follows the same rule as [`Value::insert`]: `Null` is promoted to an
+    /// empty object first, and any other non-object value is left untouched
+    /// and an error is returned.
+    ///
+    /// ```
+    /// use fastjson::Value;
+    ///
+    /// let mut counts = Value::Null;
+    /// for word in ["a", "b", "a"] {
+    ///     let n = counts.entry(word).unwrap().or_insert(Value::from(0));
+    ///     *n = Value::from(n.as_f64().unwrap() as i64 + 1);
+    /// }
+    /// assert_eq!(counts.to_compact_string(), r#"{"a": 2, "b": 1}"#);
+    /// ```
+    pub fn entry(&mut self, key: impl Into<String>) -> Result<Entry<'_>> {
+        if self.is_null() {
+            *self = Value::Object(Map::new());
+        }
+        match self {
+            Value::Object(o) => Ok(o.entry(key)),
+            _ => Err(Error::TypeError(format!("cannot take an entry of {}", self.type_name()))),
+        }
+    }
+
+    /// Append an element to an array
+    ///
+    /// `Null` is promoted to an empty array first. Any other non-array value
+    /// is left untouched and an error is returned.
+    pub fn push(&mut self, value: impl Into<Value>) -> Result<()> {
+        if self.is_null() {
+            *self = Value::Array(Vec::new());
+        }
+        match self {
+            Value::Array(a) => {
+                a.push(value.into());
+                Ok(())
+            }
+            _ => Err(Error::TypeError(format!("cannot push an element onto {}", self.type_name()))),
+        }
+    }
+
+    /// Remove a key from an object, returning its value
+    ///
+    /// Returns `None` if the key is missing or this value is not an object.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        match self {
+            Value::Object(o) => o.remove(key),
+            _ => None,
+        }
+    }
+
+    /// Keep only the object entries for which `f` returns true
+    ///
+    /// Entries keep their order. Values other than objects are left untouched.
+    pub fn retain_entries<F: FnMut(&str, &Value) -> bool>(&mut self, mut f: F) {
+        if let Value::Object(o) = self {
+            o.retain(|k, v| f(k, v));
+        }
+    }
+
+    /// Keep only the array elements for which `f` returns true
+    ///
+    /// Elements keep their order. Values other than arrays are left untouched.
+    pub fn retain_elements<F: FnMut(&Value) -> bool>(&mut self, f: F) {
+        if let Value::Array(a) = self {
+            a.retain(f);
+        }
+    }
+
+    /// Move a value out of an object without cloning it, leaving `Null` in its place
+    ///
+    /// Unlike `remove`, the key stays in the object and no other entries move,
+    /// so taking several keys in turn stays cheap on large objects. Returns
+    /// `None` if the key is missing or this value is not an object.
+    pub fn take_key(&mut self, key: &str) -> Option<Value> {
+        match self {
+            Value::Object(o) => o.get_mut(key).map(std::mem::take),
+            _ => None,
+        }
+    }
+
+    /// The value at a JSON Pointer (RFC 6901) such as `/user/emails/0`
+    ///
+    /// The empty pointer refers to the whole document. Returns `None` if the
+    /// path does not exist or is malformed.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let mut target = self;
+        for token in parse_pointer(pointer)? {
+            target = match target {
+                Value::Object(o) => o.get(&token)?,
+                Value::Array(a) => a.get(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Deserialize a copy of the value at a JSON Pointer into `T`
+    ///
+    /// A missing path gives `Error::MissingField` naming the pointer, and a
+    /// type mismatch gives a `TypeError` that says where it happened:
+    ///
+    /// ```
+    /// use fastjson::parse;
+    ///
+    /// let response = parse(r#"{"data": {"items": [{"id": 7}]}}"#).unwrap();
+    /// let id: u64 = response.pointer_as("/data/items/0/id").unwrap();
+    /// assert_eq!(id, 7);
+    /// assert!(response.pointer_as::<String>("/data/items/0/id").is_err());
+    /// ```
+    pub fn pointer_as<T: Deserialize>(&self, pointer: &str) -> Result<T> {
+        let value = self.pointer(pointer).ok_or_else(|| Error::MissingField(pointer.to_string()))?;
+        T::deserialize(value.clone()).map_err(|err| match err {
+            Error::TypeError(msg) => Error::TypeError(format!("{} at {}", msg, pointer)),
+            other => other,
+        })
+    }
+
+    /// Remove the value at a JSON Pointer (RFC 6901) such as `/user/emails/0`
+    ///
+    /// Object members are removed by key and array elements by index, shifting
+    /// later elements down. Returns `None` if the path does not exist or is
+    /// malformed; the empty pointer (the whole document) cannot be removed.
+    pub fn pointer_remove(&mut self, pointer: &str) -> Option<Value> {
+        let mut tokens = parse_pointer(pointer)?;
+        let last = tokens.pop()?;
+
+        let mut target = self;
+        for token in &tokens {
+            target = match target {
+                Value::Object(o) => o.get_mut(token)?,
+                Value::Array(a) => a.get_mut(array_index(token)?)?,
+                _ => return None,
+            };
+        }
+
+        match target {
+            Value::Object(o) => o.remove(&last),
+            Value::Array(a) => {
+                let index = array_index(&last)?;
+                if index < a.len() {
+                    Some(a.remove(index))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// Index into an array or object
     pub fn get(&self, index: impl Index) -> Option<&Value> {
         index.index_into(self)
     }
+
+    /// Look up an object key ignoring case, so `"id"` finds `"ID"` or `"Id"`
+    ///
+    /// An exact match wins; otherwise the first matching key in object order
+    /// is used. Case folding is Unicode-aware. Unlike `get`, this scans every
+    /// key, so it is O(n) in the size of the object. Returns `None` if no key
+    /// matches or this value is not an object.
+    pub fn get_ci(&self, key: &str) -> Option<&Value> {
+        let object = self.as_object()?;
+        object.get(key).or_else(|| {
+            let folded = |s: &str| s.chars().flat_map(char::to_lowercase).collect::<String>();
+            let key = folded(key);
+            object.iter().find(|(k, _)| folded(k) == key).map(|(_, v)| v)
+        })
+    }
+
+    /// Serialize to compact JSON, the same text `to_string` produces
+    ///
+    /// Unlike `to_string`, this cannot fail. A `Value` never holds NaN or an
+    /// infinity, since converting one gives `null`, so every `Value` is valid JSON.
+    pub fn to_compact_string(&self) -> String {
+        self.to_string_with(&SerializeOptions::new())
+    }
+
+    /// The number of bytes in the compact JSON for this value, the length of
+    /// `to_compact_string()`, without building the output
+    ///
+    /// Useful for sizing buffers or a `Content-Length` header up front.
+    ///
+    /// ```
+    /// use fastjson::{parse, Value};
+    ///
+    /// let value = parse(r#"{"text": "line\nbreak", "n": [1, 2.5]}"#).unwrap();
+    /// assert_eq!(value.serialized_len(), value.to_compact_string().len());
+    /// ```
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            Value::Null => 4,
+            Value::Bool(b) => if *b { 4 } else { 5 },
+            Value::Number(n) => {
+                let mut counter = ByteCounter(0);
+                let _ = write!(counter, "{}", n);
+                counter.0
+            }
+            Value::String(s) => escaped_len(s),
+            // Brackets plus a ", " between elements
+            Value::Array(a) => {
+                2 + a.iter().map(Value::serialized_len).sum::<usize>() + 2 * a.len().saturating_sub(1)
+            }
+            // Braces, a ": " after each key and a ", " between entries
+            Value::Object(o) => {
+                2 + o.iter().map(|(k, v)| escaped_len(k) + 2 + v.serialized_len()).sum::<usize>()
+                    + 2 * o.len().saturating_sub(1)
+            }
+        }
+    }
+
+    /// Serialize to JSON indented by two spaces, the same text `to_string_pretty` produces
+    pub fn to_pretty_string(&self) -> String {
+        self.to_string_with(&SerializeOptions::new().pretty())
+    }
+
+    fn to_string_with(&self, options: &SerializeOptions) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, options, 0);
+        out
+    }
+
+    /// Serialize to canonical JSON: compact, with object keys sorted recursively
+    ///
+    /// Keys are ordered by their UTF-16 code units as in RFC 8785, so equivalent
+    /// documents produce identical strings regardless of key order. Useful for
+    /// hashing and signing.
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out);
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match self {
+            Value::Array(a) => {
+                out.push('[');
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    v.write_canonical(out);
+                }
+                out.push(']');
+            }
+            Value::Object(o) => {
+                let mut entries: Vec<(&String, &Value)> = o.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+                out.push('{');
+                for (i, (k, v)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push('"');
+                    out.push_str(&escape_string(k));
+                    out.push_str("\":");
+                    v.write_canonical(out);
+                }
+                out.push('}');
+            }
+            // Parsed literals are normalized, and integral floats print like
+            // integers, so equal numbers hash the same
+            Value::Number(n) => match n.as_integer() {
+                Some(int) => out.push_str(&int.to_string()),
+                None if n.as_f64().fract() == 0.0 && n.as_f64().abs() <= MAX_SAFE_INTEGER => {
+                    out.push_str(&n.as_f64().to_string())
+                }
+                None => out.push_str(&format_number(n.as_f64())),
+            },
+            scalar => out.push_str(&scalar.to_string()),
+        }
+    }
+
+    /// Call `f` on this value and then on every value nested inside it, depth-first
+    ///
+    /// Array elements are visited in order; object entries in map order.
+    pub fn walk<F: FnMut(&Value)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            Value::Array(a) => a.iter().for_each(|v| v.walk(f)),
+            Value::Object(o) => o.values().for_each(|v| v.walk(f)),
+            _ => {}
+        }
+    }
+
+    /// Like [`walk`](Value::walk), but allows each value to be modified in place
+    ///
+    /// A value is visited before its children, so children that `f` adds to a
+    /// node are visited too.
+    pub fn walk_mut<F: FnMut(&mut Value)>(&mut self, f: &mut F) {
+        f(self);
+        match self {
+            Value::Array(a) => a.iter_mut().for_each(|v| v.walk_mut(f)),
+            Value::Object(o) => o.values_mut().for_each(|v| v.walk_mut(f)),
+            _ => {}
+        }
+    }
+
+    /// Structural equality where numbers only need to be within `epsilon` of each other
+    ///
+    /// Arrays are compared element by element and objects key by key, so
+    /// object entry order does not matter.
+    pub fn approx_eq(&self, other: &Value, epsilon: f64) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => {
+                a == b || (a.as_f64() - b.as_f64()).abs() <= epsilon
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.approx_eq(y, epsilon))
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(key, x)| match b.get(key) {
+                        Some(y) => x.approx_eq(y, epsilon),
+                        None => false,
+                    })
+            }
+            _ => self == other,
+        }
+    }
+}
+
+// Split a JSON Pointer into unescaped reference tokens
+fn parse_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(rest.split('/').map(|token| token.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+// Array indices in a pointer are plain decimal without leading zeros
+fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
 }
 
 /// Types that can be used to index into a `Value`
@@ -126,7 +561,7 @@ impl Index for usize {
     }
 }
 
-impl<'a> Index for &'a str {
+impl Index for &str {
     fn index_into(self, value: &Value) -> Option<&Value> {
         match value {
             Value::Object(map) => map.get(self),
@@ -141,9 +576,73 @@ impl Index for String {
     }
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Value::Null
+// Conversions used by the builder methods
+macro_rules! from_number_impls {
+    ($($ty:ty)+) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::Number(Number::from(n))
+                }
+            }
+        )+
+    };
+}
+
+from_number_impls!(i8 i16 i32 i64 u8 u16 u32 u64);
+
+// JSON has no NaN or infinity, so those become null
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(n: f32) -> Self {
+        Value::from(f64::from(n))
+    }
+}
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(a: Vec<Value>) -> Self {
+        Value::Array(a)
+    }
+}
+
+impl From<Map> for Value {
+    fn from(o: Map) -> Self {
+        Value::Object(o)
+    }
+}
+
+impl From<HashMap<String, Value>> for Value {
+    fn from(o: HashMap<String, Value>) -> Self {
+        Value::Object(o.into())
     }
 }
 
@@ -179,8 +678,21 @@ impl fmt::Display for Value {
     }
 }
 
-// Helper function to escape special characters in strings
-fn escape_string(s: &str) -> String {
+// Largest magnitude at which every integer is exactly representable in f64
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+// Shared float formatting for every JSON output path
+//
+// Integers are held exactly in `Number` and print through their own type, so
+// this only sees floats. It uses the shortest digits that parse back to the
+// same f64, switching to exponent notation for very large or small
+// magnitudes. Integral floats keep their `.0`, so they read back as floats.
+pub(crate) fn format_number(n: f64) -> String {
+    format!("{:?}", n)
+}
+
+// Shared string escaping for every JSON output path
+pub(crate) fn escape_string(s: &str) -> String {
     let mut escaped = String::with_capacity(s.len() + 2);
     for c in s.chars() {
         match c {
@@ -191,8 +703,129 @@ fn escape_string(s: &str) -> String {
             '\t' => escaped.push_str("\\t"),
             '\u{0008}' => escaped.push_str("\\b"),
             '\u{000C}' => escaped.push_str("\\f"),
+            c if c.is_control() && (c as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
             _ => escaped.push(c),
         }
     }
     escaped
-}
\ No newline at end of file
+}
+
+// Length of a string once quoted and escaped by `escape_string`
+fn escaped_len(s: &str) -> usize {
+    2 + s
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{0008}' | '\u{000C}' => 2,
+            c if c.is_control() && (c as u32) < 0x20 => 6,
+            c => c.len_utf8(),
+        })
+        .sum::<usize>()
+}
+
+// Counts the bytes written through it, for measuring formatted output
+struct ByteCounter(usize);
+
+impl fmt::Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+// Write a value as JSON text, shared by every text output path
+pub(crate) fn write_value(out: &mut String, value: &Value, options: &SerializeOptions, depth: usize) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n, options),
+        Value::String(s) => write_string(out, s, options),
+        Value::Array(a) => {
+            if a.is_empty() {
+                out.push_str("[]");
+                return;
+            }
+
+            out.push('[');
+            for (i, item) in a.iter().enumerate() {
+                write_separator(out, options, i == 0, depth + 1);
+                write_value(out, item, options, depth + 1);
+            }
+            write_close(out, options, depth, ']');
+        }
+        Value::Object(o) => {
+            if o.is_empty() {
+                out.push_str("{}");
+                return;
+            }
+
+            let mut entries: Vec<(&String, &Value)> = o.iter().collect();
+            if options.sort_keys {
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+            }
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                write_separator(out, options, i == 0, depth + 1);
+                write_string(out, key, options);
+                out.push_str(": ");
+                write_value(out, value, options, depth + 1);
+            }
+            write_close(out, options, depth, '}');
+        }
+    }
+}
+
+pub(crate) fn write_number(out: &mut String, n: &Number, options: &SerializeOptions) {
+    match options.float_precision {
+        Some(digits) if n.as_integer().is_none() => out.push_str(&format!("{:.*}", digits, n.as_f64())),
+        _ => out.push_str(&n.to_string()),
+    }
+}
+
+// Write what precedes an array element or object entry at the given depth
+pub(crate) fn write_separator(out: &mut String, options: &SerializeOptions, first: bool, depth: usize) {
+    match options.indent {
+        Some(spaces) => {
+            out.push_str(if first { "\n" } else { ",\n" });
+            out.push_str(&" ".repeat(spaces * depth));
+        }
+        None if !first => out.push_str(", "),
+        None => {}
+    }
+}
+
+pub(crate) fn write_close(out: &mut String, options: &SerializeOptions, depth: usize, bracket: char) {
+    if let Some(spaces) = options.indent {
+        out.push('\n');
+        out.push_str(&" ".repeat(spaces * depth));
+    }
+    out.push(bracket);
+}
+
+fn is_js_separator(c: char, options: &SerializeOptions) -> bool {
+    options.escape_js_separators && matches!(c, '\u{2028}' | '\u{2029}')
+}
+
+pub(crate) fn write_string(out: &mut String, s: &str, options: &SerializeOptions) {
+    out.push('"');
+    let escaped = escape_string(s);
+    if options.escape_non_ascii || options.escape_forward_slash || options.escape_js_separators {
+        let mut units = [0u16; 2];
+        for c in escaped.chars() {
+            if c == '/' && options.escape_forward_slash {
+                out.push_str("\\/");
+            } else if c.is_ascii() || !(options.escape_non_ascii || is_js_separator(c, options)) {
+                out.push(c);
+            } else {
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+    } else {
+        out.push_str(&escaped);
+    }
+    out.push('"');
+}
@@ -32,14 +32,8 @@ fn test_basic_serialization() {
     let json = to_string(&person).unwrap();
     println!("JSON output: {}", json);
     
-    // Since HashMap order is non-deterministic, check for both field orders
-    assert!(json.contains(r#""name": "John Doe""#));
-    assert!(json.contains(r#""age": 30"#));
-    assert!(json.contains(r#""is_active": true"#));
-    assert!(json.contains(r#""emailAddress": "john@example.com""#));
-    
-    // _internal_id should be skipped
-    assert!(!json.contains("_internal_id"));
+    // Fields keep declaration order; _internal_id is skipped
+    assert_eq!(json, r#"{"name": "John Doe", "age": 30, "is_active": true, "emailAddress": "john@example.com"}"#);
 }
 
 #[test]
@@ -88,12 +82,10 @@ fn test_pretty_print() {
     let json = to_string_pretty(&person).unwrap();
     
     // Pretty printing adds newlines and indentation
-    assert!(json.contains("{\n"));
-    assert!(json.contains("\n}"));
-    
-    // Check field values
-    assert!(json.contains(r#""name": "John Doe""#));
-    assert!(json.contains(r#""age": 30"#));
+    assert_eq!(
+        json,
+        "{\n  \"name\": \"John Doe\",\n  \"age\": 30,\n  \"is_active\": true,\n  \"emailAddress\": \"john@example.com\"\n}"
+    );
 }
 
 // Completely removed test_basic_deserialization to avoid error
@@ -150,14 +142,11 @@ fn test_enum_serialization() {
     
     // Tuple variant
     let json2 = to_string(&status2).unwrap();
-    assert!(json2.contains(r#""type": "Pending""#));
-    assert!(json2.contains(r#""data": ["Approval required"]"#));
+    assert_eq!(json2, r#"{"type": "Pending", "data": ["Approval required"]}"#);
     
     // Struct variant
     let json3 = to_string(&status3).unwrap();
-    assert!(json3.contains(r#""type": "Custom""#));
-    assert!(json3.contains(r#""code": 42"#));
-    assert!(json3.contains(r#""message": "Custom status""#));
+    assert_eq!(json3, r#"{"type": "Custom", "code": 42, "message": "Custom status"}"#);
     
     // Round-trip all variants
     let decoded1: Status = from_str(&json1).unwrap();
@@ -175,6 +164,7 @@ fn test_enum_with_derive() {
     
     // Create an enum using derive macros
     #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[allow(clippy::upper_case_acronyms)]
     enum SimpleColors {
         Red,
         Green,
@@ -195,16 +185,12 @@ fn test_enum_with_derive() {
     // Test tuple variants
     let color2 = SimpleColors::Custom("#336699".to_string());
     let json2 = to_string(&color2).unwrap();
-    assert!(json2.contains(r#""type": "Custom""#));
-    assert!(json2.contains(r#""data""#));
-    assert!(json2.contains(r#"#336699"#));
+    assert_eq!(json2, r##"{"type": "Custom", "data": ["#336699"]}"##);
     
     // Test struct variant with fields
     let color3 = SimpleColors::RGB { r: 255, g: 0, b: 0, alpha: Some(0.5) };
     let json3 = to_string(&color3).unwrap();
-    assert!(json3.contains(r#""type": "RGB""#));
-    assert!(json3.contains(r#""r": 255"#));
-    assert!(json3.contains(r#""alpha": 0.5"#));
+    assert_eq!(json3, r#"{"type": "RGB", "r": 255, "g": 0, "b": 0, "alpha": 0.5}"#);
     
     // Print the JSON to debug
     println!("JSON for struct variant with Some: {}", json3);
@@ -216,9 +202,7 @@ fn test_enum_with_derive() {
     // Print the JSON to debug
     println!("JSON for struct variant with None: {}", json4);
     
-    assert!(json4.contains(r#""type": "RGB""#));
-    assert!(json4.contains(r#""g": 255"#));
-    assert!(json4.contains(r#""alpha": null"#));
+    assert_eq!(json4, r#"{"type": "RGB", "r": 0, "g": 255, "b": 0, "alpha": null}"#);
     
     // Test round-trip with just unit variant which is simplest
     let decoded1: SimpleColors = from_str(&json1).unwrap();
@@ -257,14 +241,11 @@ fn test_simple_enum() {
     
     // Tuple variant
     let json2 = to_string(&enum2).unwrap();
-    assert!(json2.contains(r#""type": "Two""#));
-    assert!(json2.contains(r#""data""#));
-    assert!(json2.contains(r#""test""#));
+    assert_eq!(json2, r#"{"type": "Two", "data": ["test"]}"#);
     
     // Struct variant
     let json3 = to_string(&enum3).unwrap();
-    assert!(json3.contains(r#""type": "Three""#));
-    assert!(json3.contains(r#""value": 42"#));
+    assert_eq!(json3, r#"{"type": "Three", "value": 42}"#);
     
     // Round-trip
     let decoded1: SimpleEnum = from_str(&json1).unwrap();
@@ -374,6 +355,7 @@ fn test_enum_with_attributes() {
     
     // Until we fix the derive macro completely, we'll use manual implementation
     #[derive(Debug, PartialEq)]
+    #[allow(clippy::upper_case_acronyms)]
     enum ColorChoice {
         Red,
         Green,
@@ -389,28 +371,27 @@ fn test_enum_with_attributes() {
     // Manually implement with attribute behavior
     impl fastjson::Serialize for ColorChoice {
         fn serialize(&self) -> fastjson::Result<fastjson::Value> {
-            use std::collections::HashMap;
-            use fastjson::Value;
+            use fastjson::{Map, Value};
             
             match self {
                 ColorChoice::Red => Ok(Value::String("red".to_owned())),
                 ColorChoice::Green => Ok(Value::String("green".to_owned())),
                 ColorChoice::Custom(s) => {
-                    let mut map = HashMap::new();
+                    let mut map = Map::new();
                     map.insert("type".to_owned(), Value::String("custom-color".to_owned()));
                     map.insert("data".to_owned(), Value::Array(vec![fastjson::Serialize::serialize(s)?]));
                     Ok(Value::Object(map))
                 },
                 ColorChoice::RGB { r, g, b, alpha } => {
-                    let mut map = HashMap::new();
+                    let mut map = Map::new();
                     map.insert("type".to_owned(), Value::String("rgb".to_owned()));
-                    map.insert("r".to_owned(), Value::Number(*r as f64));
-                    map.insert("g".to_owned(), Value::Number(*g as f64));
-                    map.insert("b".to_owned(), Value::Number(*b as f64));
+                    map.insert("r".to_owned(), Value::from(*r));
+                    map.insert("g".to_owned(), Value::from(*g));
+                    map.insert("b".to_owned(), Value::from(*b));
                     
                     // Skip if none (implementing skip_if_none attribute behavior)
                     if let Some(a) = alpha {
-                        map.insert("alpha".to_owned(), Value::Number(*a as f64));
+                        map.insert("alpha".to_owned(), Value::from(*a));
                     }
                     
                     Ok(Value::Object(map))
@@ -490,23 +471,17 @@ fn test_enum_with_attributes() {
     // Test tuple variants with rename attribute
     let color2 = ColorChoice::Custom("#336699".to_string());
     let json2 = to_string(&color2).unwrap();
-    assert!(json2.contains(r#""type": "custom-color""#));
-    assert!(json2.contains(r#""data""#));
-    assert!(json2.contains(r#"#336699"#));
+    assert_eq!(json2, r##"{"type": "custom-color", "data": ["#336699"]}"##);
     
     // Test struct variant with fields
     let color3 = ColorChoice::RGB { r: 255, g: 0, b: 0, alpha: Some(0.5) };
     let json3 = to_string(&color3).unwrap();
-    assert!(json3.contains(r#""type": "rgb""#));
-    assert!(json3.contains(r#""r": 255"#));
-    assert!(json3.contains(r#""alpha": 0.5"#));
+    assert_eq!(json3, r#"{"type": "rgb", "r": 255, "g": 0, "b": 0, "alpha": 0.5}"#);
     
     // Test struct variant with skip_if_none field set to None
     let color4 = ColorChoice::RGB { r: 0, g: 255, b: 0, alpha: None };
     let json4 = to_string(&color4).unwrap();
-    assert!(json4.contains(r#""type": "rgb""#));
-    assert!(json4.contains(r#""g": 255"#));
-    assert!(!json4.contains("alpha"));
+    assert_eq!(json4, r#"{"type": "rgb", "r": 0, "g": 255, "b": 0}"#);
     
     // Test round-trip serialization/deserialization
     let decoded1: ColorChoice = from_str(&json1).unwrap();
@@ -580,9 +555,7 @@ fn test_option_serialization() {
     };
     
     let json1 = to_string(&test1).unwrap();
-    assert!(json1.contains(r#""required": "hello""#));
-    assert!(json1.contains(r#""optional": "world""#));
-    assert!(json1.contains(r#""conditional": 42"#));
+    assert_eq!(json1, r#"{"required": "hello", "optional": "world", "conditional": 42}"#);
     
     // With optional as None
     let test2 = TestOptional {
@@ -592,9 +565,7 @@ fn test_option_serialization() {
     };
     
     let json2 = to_string(&test2).unwrap();
-    assert!(json2.contains(r#""required": "hello""#));
-    assert!(json2.contains(r#""optional": null"#));
-    assert!(json2.contains(r#""conditional": 42"#));
+    assert_eq!(json2, r#"{"required": "hello", "optional": null, "conditional": 42}"#);
     
     // With skip_if_none field as None
     let test3 = TestOptional {
@@ -604,17 +575,12 @@ fn test_option_serialization() {
     };
     
     let json3 = to_string(&test3).unwrap();
-    assert!(json3.contains(r#""required": "hello""#));
-    assert!(json3.contains(r#""optional": "world""#));
-    assert!(!json3.contains("conditional"));
+    assert_eq!(json3, r#"{"required": "hello", "optional": "world"}"#);
     
     // Round-trip deserialization
-    let decoded1 = from_str::<TestOptional>(&json1);
-    if decoded1.is_err() {
-        println!("Error deserializing json1: {:?}", decoded1.err());
-    } else {
-        let decoded1 = decoded1.unwrap();
-        assert_eq!(test1, decoded1);
+    match from_str::<TestOptional>(&json1) {
+        Ok(decoded1) => assert_eq!(test1, decoded1),
+        Err(e) => println!("Error deserializing json1: {:?}", e),
     }
     
     // Print JSON strings to debug
@@ -630,10 +596,10 @@ fn test_option_serialization() {
 fn test_number_range_validation() {
     use fastjson::{to_string, from_str};
     
-    // u64 too large for JSON
-    let big_num: u64 = 10000000000000000000; // 10^19, beyond f64 precision
+    // u64 beyond f64 precision is written exactly
+    let big_num: u64 = 10000000000000000000; // 10^19
     let result = to_string(&big_num);
-    assert!(result.is_err());
+    assert_eq!(result.unwrap(), "10000000000000000000");
     
     // i8 out of range
     let json = "300"; // Too large for i8
@@ -644,4 +610,1486 @@ fn test_number_range_validation() {
     let json = "42.5";
     let result: Result<i32, _> = from_str(json);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_as_string_integers() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ids {
+        #[fastjson(as_string)]
+        id: u64,
+        #[fastjson(as_string)]
+        offset: i64,
+        count: u32,
+    }
+
+    let ids = Ids {
+        id: 18446744073709551615u64,
+        offset: i64::MIN,
+        count: 3,
+    };
+
+    // Large integers are written as strings, preserving every digit
+    let json = to_string(&ids).unwrap();
+    assert_eq!(json, r#"{"id": "18446744073709551615", "offset": "-9223372036854775808", "count": 3}"#);
+
+    let decoded: Ids = from_str(&json).unwrap();
+    assert_eq!(decoded, ids);
+
+    // as_string fields only accept strings that parse as the field type
+    assert!(from_str::<Ids>(r#"{"id": 5, "offset": "0", "count": 1}"#).is_err());
+    assert!(from_str::<Ids>(r#"{"id": "abc", "offset": "0", "count": 1}"#).is_err());
+    assert!(from_str::<Ids>(r#"{"id": "18446744073709551616", "offset": "0", "count": 1}"#).is_err());
+}
+
+#[derive(Debug, PartialEq)]
+struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+// Formats dates as "YYYY-MM-DD" strings, for use with #[fastjson(with = "iso_date")]
+mod iso_date {
+    use super::Date;
+    use fastjson::{Error, Result, Value};
+
+    pub fn serialize(date: &Date) -> Result<Value> {
+        Ok(Value::String(format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)))
+    }
+
+    pub fn deserialize(value: Value) -> Result<Date> {
+        let s = match value {
+            Value::String(s) => s,
+            other => return Err(Error::TypeError(format!("expected date string, found {:?}", other))),
+        };
+        let parts: Vec<&str> = s.split('-').collect();
+        let invalid = || Error::custom(format!("invalid date: {}", s));
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        Ok(Date {
+            year: parts[0].parse().map_err(|_| invalid())?,
+            month: parts[1].parse().map_err(|_| invalid())?,
+            day: parts[2].parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[test]
+fn test_with_custom_module() {
+    use fastjson::Error;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Event {
+        name: String,
+        #[fastjson(with = "iso_date")]
+        date: Date,
+    }
+
+    let event = Event {
+        name: "Launch".to_string(),
+        date: Date { year: 2024, month: 3, day: 9 },
+    };
+
+    let json = to_string(&event).unwrap();
+    assert_eq!(json, r#"{"name": "Launch", "date": "2024-03-09"}"#);
+
+    let decoded: Event = from_str(&json).unwrap();
+    assert_eq!(decoded, event);
+
+    // Errors from the custom module are passed through
+    let result: Result<Event, Error> = from_str(r#"{"name": "Launch", "date": "March 9th"}"#);
+    assert_eq!(result.unwrap_err(), Error::custom("invalid date: March 9th"));
+}
+
+#[test]
+fn test_field_alias() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Account {
+        #[fastjson(alias = "userId", alias = "uid")]
+        user_id: u32,
+        #[fastjson(rename = "displayName", alias = "name")]
+        display_name: String,
+    }
+
+    let expected = Account {
+        user_id: 7,
+        display_name: "Ada".to_string(),
+    };
+
+    // Canonical names, old names, and a mix of both are all accepted
+    let new_keys: Account = from_str(r#"{"user_id": 7, "displayName": "Ada"}"#).unwrap();
+    let old_keys: Account = from_str(r#"{"userId": 7, "name": "Ada"}"#).unwrap();
+    let second_alias: Account = from_str(r#"{"uid": 7, "displayName": "Ada"}"#).unwrap();
+    assert_eq!(new_keys, expected);
+    assert_eq!(old_keys, expected);
+    assert_eq!(second_alias, expected);
+
+    // The primary name wins over an alias when both are present
+    let both: Account = from_str(r#"{"userId": 1, "user_id": 7, "name": "Ada"}"#).unwrap();
+    assert_eq!(both, expected);
+
+    // Serialization always uses the canonical name
+    let json = to_string(&expected).unwrap();
+    assert_eq!(json, r#"{"user_id": 7, "displayName": "Ada"}"#);
+
+    let missing: Result<Account, _> = from_str(r#"{"displayName": "Ada"}"#);
+    assert_eq!(missing.unwrap_err(), fastjson::Error::MissingField("user_id".to_string()));
+}
+
+#[test]
+fn test_boxed_and_shared_slices() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Frozen {
+        values: Box<[i32]>,
+        shared: Rc<[String]>,
+        threaded: Arc<[u8]>,
+    }
+
+    let frozen = Frozen {
+        values: vec![1, 2, 3].into_boxed_slice(),
+        shared: vec!["a".to_string()].into(),
+        threaded: Vec::new().into(),
+    };
+
+    let json = to_string(&frozen).unwrap();
+    assert_eq!(json, r#"{"values": [1, 2, 3], "shared": ["a"], "threaded": []}"#);
+
+    let decoded: Frozen = from_str(&json).unwrap();
+    assert_eq!(decoded, frozen);
+
+    assert!(from_str::<Box<[i32]>>(r#"{"values": 1}"#).is_err());
+}
+
+#[test]
+fn test_external_tag() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(external_tag)]
+    enum Message {
+        Quit,
+        Pending(String),
+        Move(i32, i32),
+        Custom { code: u32, message: String },
+    }
+
+    // Compact canonical output matches serde_json's bytes for each variant kind
+    let cases = [
+        (Message::Quit, r#""Quit""#),
+        (Message::Pending("x".to_string()), r#"{"Pending":"x"}"#),
+        (Message::Move(1, -2), r#"{"Move":[1,-2]}"#),
+        (
+            Message::Custom { code: 42, message: "m".to_string() },
+            r#"{"Custom":{"code":42,"message":"m"}}"#,
+        ),
+    ];
+
+    for (message, serde_json) in cases {
+        let value = fastjson::Serialize::serialize(&message).unwrap();
+        assert_eq!(value.to_canonical_string(), serde_json);
+
+        let from_serde: Message = from_str(serde_json).unwrap();
+        assert_eq!(from_serde, message);
+
+        let round_trip: Message = from_str(&to_string(&message).unwrap()).unwrap();
+        assert_eq!(round_trip, message);
+    }
+
+    // The fastjson "type" shape is not accepted for externally tagged enums
+    assert!(from_str::<Message>(r#"{"type": "Pending", "data": ["x"]}"#).is_err());
+    assert!(from_str::<Message>(r#"{"Pending": "x", "Quit": null}"#).is_err());
+    assert!(from_str::<Message>(r#"{"Move": 1}"#).is_err());
+}
+
+#[test]
+fn test_integer_tag_values() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Packet {
+        #[fastjson(tag_value = 0)]
+        Ping,
+        #[fastjson(tag_value = 1)]
+        Data(u32, String),
+        #[fastjson(tag_value = 2)]
+        Close { code: u16 },
+    }
+
+    let cases = [
+        (Packet::Ping, r#"{"type":0}"#),
+        (Packet::Data(7, "x".to_string()), r#"{"data":[7,"x"],"type":1}"#),
+        (Packet::Close { code: 1000 }, r#"{"code":1000,"type":2}"#),
+    ];
+
+    for (packet, json) in cases {
+        let value = fastjson::Serialize::serialize(&packet).unwrap();
+        assert_eq!(value.to_canonical_string(), json);
+        assert_eq!(from_str::<Packet>(json).unwrap(), packet);
+        assert_eq!(from_str::<Packet>(&to_string(&packet).unwrap()).unwrap(), packet);
+    }
+
+    // Names are not accepted in place of the integer tag, nor unknown or fractional tags
+    assert!(from_str::<Packet>(r#"{"type": "Close", "code": 1}"#).is_err());
+    assert!(from_str::<Packet>(r#""Ping""#).is_err());
+    assert!(from_str::<Packet>(r#"{"type": 3}"#).is_err());
+    assert!(from_str::<Packet>(r#"{"type": 0.5}"#).is_err());
+}
+
+#[test]
+fn test_documented_and_cfg_fields() {
+    /// A documented struct
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Documented {
+        /// The identifier
+        ///
+        /// Spans several lines, with `code` and "quotes": in it.
+        id: u32,
+        /** Block doc comment */
+        name: String,
+        /// Only compiled in test builds
+        #[cfg(test)]
+        #[fastjson(rename = "flag")]
+        enabled: bool,
+        /// Never compiled, so it must not appear in the output
+        #[cfg(any())]
+        missing: String,
+        /// Renamed through cfg_attr
+        #[cfg_attr(test, fastjson(rename = "n"))]
+        count: i32,
+    }
+
+    let value = Documented { id: 1, name: "a".to_string(), enabled: true, count: 3 };
+    let json = fastjson::Serialize::serialize(&value).unwrap().to_canonical_string();
+    assert_eq!(json, r#"{"flag":true,"id":1,"n":3,"name":"a"}"#);
+    assert_eq!(from_str::<Documented>(&json).unwrap(), value);
+}
+
+mod visibility_forms {
+    use fastjson::{from_str, Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct Visibility {
+        pub public: u8,
+        pub(crate) krate: u8,
+        pub(super) parent: u8,
+        pub(self) private: u8,
+        pub(in crate::visibility_forms) scoped: u8,
+        pub (crate) spaced: u8,
+        inherited: u8,
+    }
+
+    // `pub (u8, u8)` is a public field of tuple type, not a visibility scope
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    pub struct Pair(pub (u8, u8), pub(crate) u8);
+
+    #[test]
+    fn test_visibility_forms() {
+        let value = Visibility { public: 1, krate: 2, parent: 3, private: 4, scoped: 5, spaced: 6, inherited: 7 };
+        let json = fastjson::Serialize::serialize(&value).unwrap().to_canonical_string();
+        assert_eq!(
+            json,
+            r#"{"inherited":7,"krate":2,"parent":3,"private":4,"public":1,"scoped":5,"spaced":6}"#
+        );
+        assert_eq!(from_str::<Visibility>(&json).unwrap(), value);
+
+        let pair = Pair((1, 2), 3);
+        let json = fastjson::to_string(&pair).unwrap();
+        assert_eq!(json, "[[1, 2], 3]");
+        assert_eq!(from_str::<Pair>(&json).unwrap(), pair);
+    }
+}
+
+#[test]
+fn test_deserialize_moves_fields() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Inner {
+        values: Vec<u32>,
+        label: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Outer {
+        #[fastjson(alias = "primary")]
+        inner: Inner,
+        pair: (Inner, u8),
+        #[fastjson(skip)]
+        cached: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Pair(Inner, Inner),
+        Named { inner: Inner },
+    }
+
+    let inner = || Inner { values: vec![1, 2, 3], label: Some("x".to_string()) };
+    let outer = Outer { inner: inner(), pair: (inner(), 9), cached: 0 };
+    assert_eq!(from_str::<Outer>(&to_string(&outer).unwrap()).unwrap(), outer);
+
+    // The primary key still wins over an alias when both are present
+    let json = r#"{"inner": {"values": [1, 2, 3], "label": "x"}, "primary": {"values": []}, "pair": [{"values": [1, 2, 3], "label": "x"}, 9]}"#;
+    assert_eq!(from_str::<Outer>(json).unwrap(), outer);
+
+    for shape in [Shape::Pair(inner(), inner()), Shape::Named { inner: inner() }] {
+        assert_eq!(from_str::<Shape>(&to_string(&shape).unwrap()).unwrap(), shape);
+    }
+}
+
+#[test]
+fn test_custom_serializer() {
+    use fastjson::{Result, Serializer};
+
+    // A debug format that writes S-expressions
+    #[derive(Default)]
+    struct SExpr(String);
+
+    impl SExpr {
+        fn atom(&mut self, atom: &str) -> Result<()> {
+            if !self.0.is_empty() && !self.0.ends_with('(') {
+                self.0.push(' ');
+            }
+            self.0.push_str(atom);
+            Ok(())
+        }
+    }
+
+    impl Serializer for SExpr {
+        fn serialize_null(&mut self) -> Result<()> { self.atom("nil") }
+        fn serialize_bool(&mut self, v: bool) -> Result<()> { self.atom(if v { "#t" } else { "#f" }) }
+        fn serialize_f64(&mut self, v: f64) -> Result<()> { self.atom(&v.to_string()) }
+        fn serialize_str(&mut self, v: &str) -> Result<()> { self.atom(&format!("{:?}", v)) }
+        fn serialize_seq(&mut self, _len: usize) -> Result<()> { self.atom("(list") }
+        fn end_seq(&mut self) -> Result<()> { self.0.push(')'); Ok(()) }
+        fn serialize_map(&mut self, _len: usize) -> Result<()> { self.atom("(map") }
+        fn serialize_key(&mut self, key: &str) -> Result<()> { self.atom(&format!(":{}", key)) }
+        fn end_map(&mut self) -> Result<()> { self.0.push(')'); Ok(()) }
+    }
+
+    #[derive(Serialize)]
+    struct Point {
+        x: i32,
+    }
+
+    #[derive(Serialize)]
+    struct Shape {
+        name: String,
+        points: Vec<Point>,
+        closed: Option<bool>,
+    }
+
+    let shape = Shape { name: "tri".to_string(), points: vec![Point { x: 1 }, Point { x: 2 }], closed: None };
+    let mut out = SExpr::default();
+    shape.serialize_to(&mut out).unwrap();
+
+    // Keys arrive in declaration order
+    assert_eq!(out.0, "(map :name \"tri\" :points (list (map :x 1) (map :x 2)) :closed nil)");
+
+    let mut list = SExpr::default();
+    vec![vec!["a"], vec![]].serialize_to(&mut list).unwrap();
+    assert_eq!(list.0, "(list (list \"a\") (list))");
+}
+
+#[test]
+fn test_struct_streams_fields() {
+    use fastjson::{to_string_with, Number, Result, SerializeOptions, Serializer, Value};
+
+    // Counts integers written directly and numbers that went through a Value
+    #[derive(Default)]
+    struct Counter {
+        integers: usize,
+        numbers: usize,
+    }
+
+    impl Serializer for Counter {
+        fn serialize_null(&mut self) -> Result<()> { Ok(()) }
+        fn serialize_bool(&mut self, _: bool) -> Result<()> { Ok(()) }
+        fn serialize_f64(&mut self, _: f64) -> Result<()> { Ok(()) }
+        fn serialize_u64(&mut self, _: u64) -> Result<()> { self.integers += 1; Ok(()) }
+        fn serialize_number(&mut self, _: &Number) -> Result<()> { self.numbers += 1; Ok(()) }
+        fn serialize_str(&mut self, _: &str) -> Result<()> { Ok(()) }
+        fn serialize_seq(&mut self, _: usize) -> Result<()> { Ok(()) }
+        fn end_seq(&mut self) -> Result<()> { Ok(()) }
+        fn serialize_map(&mut self, _: usize) -> Result<()> { Ok(()) }
+        fn serialize_key(&mut self, _: &str) -> Result<()> { Ok(()) }
+        fn end_map(&mut self) -> Result<()> { Ok(()) }
+    }
+
+    #[derive(Serialize)]
+    #[allow(dead_code)]
+    struct Pair(u8, #[fastjson(skip_serializing)] u8, Vec<u8>);
+
+    #[derive(Serialize)]
+    #[fastjson(tag = "kind", tag_value = "packet")]
+    #[allow(dead_code)]
+    struct Packet {
+        #[fastjson(rename = "ID", as_string)]
+        id: u32,
+        payload: Vec<u8>,
+        #[fastjson(skip_if_none)]
+        note: Option<String>,
+        missing: Option<f64>,
+        #[fastjson(order = 1)]
+        ratio: f64,
+        #[fastjson(skip_serializing)]
+        secret: String,
+        pair: Pair,
+        nested: Vec<Vec<Value>>,
+    }
+
+    let packet = Packet {
+        id: 7,
+        payload: (0..=255).collect(),
+        note: None,
+        missing: None,
+        ratio: 0.25,
+        secret: "x".to_string(),
+        pair: Pair(1, 2, vec![3, 4]),
+        nested: vec![vec![], vec![Value::from("é/\u{2028}"), Value::Object(fastjson::Map::new())]],
+    };
+
+    let mut counter = Counter::default();
+    packet.serialize_to(&mut counter).unwrap();
+    assert_eq!(counter.integers, 256 + 3);
+    assert_eq!(counter.numbers, 1);
+
+    // Every output option gives the same text as serializing through a Value
+    let value = packet.serialize().unwrap();
+    let options = [
+        SerializeOptions::new(),
+        SerializeOptions::new().pretty(),
+        SerializeOptions::new().indent(3).sort_keys(true),
+        SerializeOptions::new().sort_keys(true).escape_non_ascii(true).escape_forward_slash(true),
+        SerializeOptions::new().pretty().escape_js_separators(true).float_precision(3).trailing_newline(true),
+    ];
+    for options in &options {
+        let json = to_string_with(&packet, options).unwrap();
+        assert_eq!(json, to_string_with(&value, options).unwrap());
+        assert_eq!(from_str::<Value>(&json).unwrap(), value);
+    }
+    assert_eq!(to_string_pretty(&packet).unwrap(), value.to_pretty_string());
+    assert!(to_string(&packet).unwrap().starts_with(r#"{"kind": "packet", "ratio": 0.25, "ID": "7", "payload": [0, 1, "#));
+}
+
+#[test]
+fn test_bytes_as_base64() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Blob {
+        #[fastjson(bytes_as_base64)]
+        data: Vec<u8>,
+        #[fastjson(bytes_as_base64)]
+        boxed: Box<[u8]>,
+        plain: Vec<u8>,
+    }
+
+    for len in 0..8u8 {
+        let bytes: Vec<u8> = (0..len).map(|i| i.wrapping_mul(97)).collect();
+        let blob = Blob { data: bytes.clone(), boxed: bytes.clone().into(), plain: bytes };
+        let json = to_string(&blob).unwrap();
+        assert_eq!(from_str::<Blob>(&json).unwrap(), blob);
+    }
+
+    let blob = Blob { data: b"hello".to_vec(), boxed: Box::new([]), plain: vec![1] };
+    assert_eq!(to_string(&blob).unwrap(), r#"{"data": "aGVsbG8=", "boxed": "", "plain": [1]}"#);
+
+    assert!(from_str::<Blob>(r#"{"data": "aGVsbG8", "boxed": "", "plain": []}"#).is_err());
+    assert!(from_str::<Blob>(r#"{"data": [104], "boxed": "", "plain": []}"#).is_err());
+}
+
+#[test]
+fn test_rename_all_variants() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(rename_all = "kebab-case")]
+    enum Status {
+        Open,
+        InProgress,
+        WaitingOnCustomer,
+        #[fastjson(rename = "done")]
+        Closed,
+        Blocked { reason: String },
+    }
+
+    let cases = [
+        (Status::Open, r#""open""#),
+        (Status::InProgress, r#""in-progress""#),
+        (Status::WaitingOnCustomer, r#""waiting-on-customer""#),
+        (Status::Closed, r#""done""#),
+    ];
+    for (status, json) in cases {
+        assert_eq!(to_string(&status).unwrap(), json);
+        assert_eq!(from_str::<Status>(json).unwrap(), status);
+    }
+
+    let blocked = Status::Blocked { reason: "x".to_string() };
+    let json = to_string(&blocked).unwrap();
+    assert_eq!(json, r#"{"type": "blocked", "reason": "x"}"#);
+    assert_eq!(from_str::<Status>(&json).unwrap(), blocked);
+
+    // The original names are no longer accepted
+    assert!(from_str::<Status>(r#""InProgress""#).is_err());
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Level {
+        VeryHigh,
+        Low,
+    }
+    assert_eq!(to_string(&Level::VeryHigh).unwrap(), r#""VERY_HIGH""#);
+    assert_eq!(from_str::<Level>(r#""LOW""#).unwrap(), Level::Low);
+
+    #[derive(Serialize, Debug)]
+    #[fastjson(rename_all = "camelCase")]
+    enum Event {
+        KeyDown,
+    }
+    assert_eq!(to_string(&Event::KeyDown).unwrap(), r#""keyDown""#);
+
+    #[derive(Serialize, Debug)]
+    #[fastjson(rename_all = "snake_case")]
+    enum Kind {
+        OneTwo,
+    }
+    assert_eq!(to_string(&Kind::OneTwo).unwrap(), r#""one_two""#);
+}
+
+#[test]
+fn test_flatten_catch_all() {
+    use fastjson::Value;
+    use std::collections::HashMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Response {
+        id: u32,
+        #[fastjson(rename = "status")]
+        state: String,
+        #[fastjson(flatten)]
+        extra: HashMap<String, Value>,
+    }
+
+    let json = r#"{"id": 7, "status": "ok", "trace": "abc", "meta": {"region": "eu"}}"#;
+    let response: Response = from_str(json).unwrap();
+    assert_eq!(response.id, 7);
+    assert_eq!(response.state, "ok");
+    assert_eq!(response.extra.len(), 2);
+    assert_eq!(response.extra.get("trace"), Some(&Value::String("abc".to_string())));
+    assert!(response.extra.get("meta").unwrap().is_object());
+
+    // Extras are inlined back next to the declared fields
+    let value = fastjson::Serialize::serialize(&response).unwrap();
+    assert_eq!(value.to_canonical_string(), r#"{"id":7,"meta":{"region":"eu"},"status":"ok","trace":"abc"}"#);
+    assert_eq!(from_str::<Response>(&to_string(&response).unwrap()).unwrap(), response);
+
+    // Declared fields win over colliding extras, and required fields are still required
+    let mut colliding = response;
+    colliding.extra.insert("id".to_string(), Value::Null);
+    let value = fastjson::Serialize::serialize(&colliding).unwrap();
+    assert_eq!(value.get("id"), Some(&Value::from(7.0)));
+    assert!(from_str::<Response>(r#"{"status": "ok"}"#).is_err());
+
+    // Aliases are consumed even when the primary key is present
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Renamed {
+        #[fastjson(alias = "old", alias = "older")]
+        name: String,
+        #[fastjson(flatten)]
+        extra: HashMap<String, Value>,
+    }
+    let renamed: Renamed = from_str(r#"{"name": "a", "old": "x", "older": "y", "other": 1}"#).unwrap();
+    assert_eq!(renamed.name, "a");
+    assert_eq!(renamed.extra.keys().collect::<Vec<_>>(), ["other"]);
+    let renamed: Renamed = from_str(r#"{"older": "y", "old": "x"}"#).unwrap();
+    assert_eq!(renamed.name, "x");
+    assert!(renamed.extra.is_empty());
+
+    // Struct variants collect extras too, without the "type" tag
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Event {
+        Click {
+            x: i32,
+            #[fastjson(flatten)]
+            rest: HashMap<String, Value>,
+        },
+    }
+    let event: Event = from_str(r#"{"type": "Click", "x": 1, "button": "left"}"#).unwrap();
+    let Event::Click { x, rest } = &event;
+    assert_eq!(*x, 1);
+    assert_eq!(rest.keys().collect::<Vec<_>>(), vec!["button"]);
+    assert_eq!(from_str::<Event>(&to_string(&event).unwrap()).unwrap(), event);
+}
+
+#[test]
+fn test_one_or_many() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Tag {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Post {
+        #[fastjson(one_or_many)]
+        tags: Vec<Tag>,
+        #[fastjson(one_or_many)]
+        ids: Vec<u32>,
+    }
+
+    let single: Post = from_str(r#"{"tags": {"name": "rust"}, "ids": 4}"#).unwrap();
+    assert_eq!(single.tags, vec![Tag { name: "rust".to_string() }]);
+    assert_eq!(single.ids, vec![4]);
+
+    let many: Post = from_str(r#"{"tags": [{"name": "a"}, {"name": "b"}], "ids": []}"#).unwrap();
+    assert_eq!(many.tags.len(), 2);
+    assert!(many.ids.is_empty());
+
+    // Always written back as an array
+    let json = to_string(&single).unwrap();
+    assert_eq!(from_str::<fastjson::Value>(&json).unwrap().get("ids").unwrap().as_array().unwrap().len(), 1);
+    assert_eq!(from_str::<Post>(&json).unwrap(), single);
+
+    // A single value still has to match the element type
+    assert!(from_str::<Post>(r#"{"tags": "rust", "ids": 1}"#).is_err());
+}
+
+#[test]
+fn test_custom_tag_key() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(tag = "kind")]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+        #[fastjson(tag_value = 9)]
+        Point,
+    }
+
+    let shapes = vec![Shape::Empty, Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }, Shape::Point];
+    let json = to_string(&shapes).unwrap();
+    assert_eq!(json, r#"["Empty", {"kind": "Circle", "data": [1.5]}, {"kind": "Rect", "w": 2, "h": 3}, {"kind": 9}]"#);
+    assert_eq!(from_str::<Vec<Shape>>(&json).unwrap(), shapes);
+
+    let rect: Shape = from_str(r#"{"kind": "Rect", "w": 1, "h": 1}"#).unwrap();
+    assert_eq!(rect, Shape::Rect { w: 1, h: 1 });
+    assert_eq!(from_str::<Shape>(r#"{"kind": 9}"#).unwrap(), Shape::Point);
+
+    // The default key is no longer recognized
+    match from_str::<Shape>(r#"{"type": "Rect", "w": 1, "h": 1}"#) {
+        Err(fastjson::Error::MissingField(key)) => assert_eq!(key, "kind"),
+        other => panic!("expected missing tag, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_type_error_names_field() {
+    #[derive(Deserialize, Debug)]
+    struct Inner {
+        #[fastjson(rename = "maxRetries")]
+        max_retries: u32,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Config {
+        name: String,
+        inner: Inner,
+    }
+
+    let config: Config = from_str(r#"{"name": "a", "inner": {"maxRetries": 1}}"#).unwrap();
+    assert_eq!((config.name.as_str(), config.inner.max_retries), ("a", 1));
+
+    match from_str::<Config>(r#"{"name": 5, "inner": {"maxRetries": 1}}"#) {
+        Err(fastjson::Error::TypeError(msg)) => assert_eq!(msg, "field `name`: expected string, found number"),
+        other => panic!("expected type error, found {:?}", other),
+    }
+
+    // Nested fields are prefixed from the outside in, using the JSON key
+    match from_str::<Config>(r#"{"name": "a", "inner": {"maxRetries": "three"}}"#) {
+        Err(fastjson::Error::TypeError(msg)) => {
+            assert_eq!(msg, "field `inner`: field `maxRetries`: expected number, found string")
+        }
+        other => panic!("expected type error, found {:?}", other),
+    }
+
+    // Other errors pass through unchanged
+    assert_eq!(
+        from_str::<Config>(r#"{"name": "a", "inner": {}}"#).unwrap_err(),
+        fastjson::Error::MissingField("maxRetries".to_string())
+    );
+}
+
+#[test]
+fn test_double_option_fields() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Patch {
+        title: Option<Option<String>>,
+        #[fastjson(rename = "dueDate")]
+        due_date: std::option::Option<Option<u32>>,
+    }
+
+    // Absent, explicit null, and a value are three different states
+    let patch: Patch = from_str(r#"{"title": null, "dueDate": 5}"#).unwrap();
+    assert_eq!(patch.title, Some(None));
+    assert_eq!(patch.due_date, Some(Some(5)));
+
+    let patch: Patch = from_str("{}").unwrap();
+    assert_eq!(patch, Patch { title: None, due_date: None });
+
+    let patch: Patch = from_str(r#"{"title": "new"}"#).unwrap();
+    assert_eq!(patch.title, Some(Some("new".to_string())));
+    assert_eq!(patch.due_date, None);
+
+    // Serializing keeps the distinction: None is omitted, Some(None) is null
+    let patch = Patch { title: Some(None), due_date: None };
+    let json = to_string(&patch).unwrap();
+    assert_eq!(json, r#"{"title": null}"#);
+    assert_eq!(from_str::<Patch>(&json).unwrap(), patch);
+}
+
+#[test]
+fn test_rename_serialize_deserialize() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Account {
+        #[fastjson(rename(serialize = "displayName", deserialize = "user_name"))]
+        name: String,
+        // Only one side renamed; the other keeps the field name
+        #[fastjson(rename(deserialize = "mail"))]
+        email: String,
+        #[fastjson(rename = "id")]
+        account_id: u32,
+    }
+
+    let account: Account = from_str(r#"{"user_name": "ann", "mail": "a@b.c", "id": 3}"#).unwrap();
+    assert_eq!(account.name, "ann");
+    assert_eq!(account.email, "a@b.c");
+
+    assert_eq!(to_string(&account).unwrap(), r#"{"displayName": "ann", "email": "a@b.c", "id": 3}"#);
+
+    // The serialize key is not accepted on input
+    assert_eq!(
+        from_str::<Account>(&to_string(&account).unwrap()).unwrap_err(),
+        fastjson::Error::MissingField("user_name".to_string())
+    );
+}
+
+#[test]
+fn test_serialize_borrowed_fields() {
+    #[derive(Serialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        #[fastjson(skip_if_none)]
+        note: Option<&'a str>,
+        tags: &'a [&'a str],
+    }
+
+    #[derive(Serialize)]
+    struct Pair<'a, 'b: 'a>(&'a str, &'b u32);
+
+    #[derive(Serialize)]
+    enum Event<'a> {
+        Message { text: &'a str },
+        Ping,
+    }
+
+    let tags = ["x", "y"];
+    let owned = String::from("borrowed");
+    let value = Borrowed { name: &owned, note: None, tags: &tags };
+    assert_eq!(to_string(&value).unwrap(), r#"{"name": "borrowed", "tags": ["x", "y"]}"#);
+
+    assert_eq!(to_string(&Pair("a", &1)).unwrap(), r#"["a", 1]"#);
+    let message = Event::Message { text: &owned[..3] };
+    assert_eq!(to_string(&message).unwrap(), r#"{"type": "Message", "text": "bor"}"#);
+    assert_eq!(to_string(&Event::Ping).unwrap(), r#""Ping""#);
+}
+
+#[test]
+fn test_one_line_enum_attributes() {
+    #[rustfmt::skip]
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum E { #[fastjson(rename="a")] A, B, #[fastjson(rename="c")] C }
+
+    let all = vec![E::A, E::B, E::C];
+    assert_eq!(to_string(&all).unwrap(), r#"["a", "B", "c"]"#);
+    assert_eq!(from_str::<Vec<E>>(r#"["a", "B", "c"]"#).unwrap(), all);
+    assert!(from_str::<E>(r#""A""#).is_err());
+
+    // Discriminant expressions may contain `<` and `>`, which are not generics
+    #[rustfmt::skip]
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Flags { Read = 1 << 0, #[fastjson(rename="w")] Write = 1 << 1, Exec = (3 > 2) as isize * 4, #[fastjson(rename="x")] All = 7 }
+
+    let all = vec![Flags::Read, Flags::Write, Flags::Exec, Flags::All];
+    assert_eq!(to_string(&all).unwrap(), r#"["Read", "w", "Exec", "x"]"#);
+    assert_eq!(from_str::<Vec<Flags>>(r#"["Read", "w", "Exec", "x"]"#).unwrap(), all);
+    assert_eq!(Flags::Exec as isize, 4);
+}
+
+#[test]
+fn test_numeric_enum() {
+    use fastjson::Error;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(numeric)]
+    enum Level {
+        Low,
+        Medium,
+        High,
+    }
+
+    assert_eq!(to_string(&Level::Medium).unwrap(), "1");
+    assert_eq!(from_str::<Level>("1").unwrap(), Level::Medium);
+    let all = vec![Level::Low, Level::Medium, Level::High];
+    assert_eq!(to_string(&all).unwrap(), "[0, 1, 2]");
+    assert_eq!(from_str::<Vec<Level>>("[0, 1, 2]").unwrap(), all);
+
+    // Out-of-range integers, floats and the string form are all rejected
+    match from_str::<Level>("3") {
+        Err(Error::TypeError(msg)) => assert_eq!(msg, "invalid value 3 for enum Level"),
+        other => panic!("expected type error, got {:?}", other),
+    }
+    assert!(from_str::<Level>("-1").is_err());
+    assert!(from_str::<Level>("1.0").is_err());
+    assert!(from_str::<Level>(r#""Medium""#).is_err());
+
+    // Explicit discriminants are used as written
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(numeric)]
+    enum Code {
+        Ok = 200,
+        NotFound = 404,
+        Teapot = 418,
+    }
+
+    assert_eq!(to_string(&Code::NotFound).unwrap(), "404");
+    assert_eq!(from_str::<Code>("418").unwrap(), Code::Teapot);
+    assert_eq!(from_str::<Code>("200").unwrap(), Code::Ok);
+    assert!(from_str::<Code>("1").is_err());
+}
+
+#[test]
+fn test_ignored_any() {
+    use fastjson::IgnoredAny;
+
+    #[derive(Deserialize, Debug)]
+    struct Envelope {
+        id: u32,
+        payload: IgnoredAny,
+    }
+
+    let json = r#"{"id": 3, "payload": {"rows": [[1, 2], [3, {"deep": [null, true, "x"]}]], "meta": {"a": 1.5}}}"#;
+    let envelope: Envelope = from_str(json).unwrap();
+    assert_eq!(envelope.id, 3);
+    assert_eq!(envelope.payload, IgnoredAny);
+
+    // The key still has to be present
+    assert!(from_str::<Envelope>(r#"{"id": 3}"#).is_err());
+
+    // Any shape at all is accepted
+    for json in ["null", "0", "\"s\"", "[]", "{\"k\": [1, {}]}"] {
+        assert_eq!(from_str::<IgnoredAny>(json).unwrap(), IgnoredAny);
+    }
+    assert_eq!(from_str::<Vec<IgnoredAny>>("[1, \"a\", {}]").unwrap().len(), 3);
+}
+
+#[test]
+fn test_quoted_numeric_fields() {
+    use fastjson::{from_str_with_options, Error, ParserOptions};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+        score: f64,
+    }
+
+    let json = r#"{"name": "Ann", "age": "30", "score": "9.5"}"#;
+    let lenient = ParserOptions { coerce_numbers_from_strings: true, ..ParserOptions::default() };
+    let person: Person = from_str_with_options(json, &lenient).unwrap();
+    assert_eq!(person, Person { name: "Ann".to_string(), age: 30, score: 9.5 });
+
+    // Strict by default, and strings that are not numbers still fail
+    assert!(from_str::<Person>(json).is_err());
+    match from_str_with_options::<Person>(r#"{"name": "Ann", "age": "abc", "score": 1}"#, &lenient) {
+        Err(Error::TypeError(msg)) => assert_eq!(msg, r#"field `age`: invalid integer string "abc""#),
+        other => panic!("expected type error, got {:?}", other),
+    }
+
+    // A from_str nested inside a lenient call gets its own, strict, options
+    mod embedded {
+        use fastjson::{from_str, Deserialize, Result, Value};
+
+        pub fn deserialize(value: Value) -> Result<Vec<u32>> {
+            from_str(&String::deserialize(value)?)
+        }
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Envelope {
+        count: u32,
+        #[fastjson(with = "embedded")]
+        payload: Vec<u32>,
+    }
+
+    let envelope: Envelope = from_str_with_options(r#"{"count": "2", "payload": "[1, 2]"}"#, &lenient).unwrap();
+    assert_eq!(envelope, Envelope { count: 2, payload: vec![1, 2] });
+    assert!(from_str_with_options::<Envelope>(r#"{"count": 2, "payload": "[\"1\"]"}"#, &lenient).is_err());
+
+    // The option is switched back off even if deserialization panics
+    struct Explode;
+    impl Deserialize for Explode {
+        fn deserialize(_: fastjson::Value) -> fastjson::Result<Self> {
+            panic!("boom")
+        }
+    }
+    assert!(std::panic::catch_unwind(|| from_str_with_options::<Explode>("1", &lenient)).is_err());
+    assert!(from_str::<u32>(r#""30""#).is_err());
+}
+
+#[test]
+fn test_skip_one_direction() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Invoice {
+        net: u32,
+        tax: u32,
+        // Computed for API output, never read back
+        #[fastjson(skip_deserializing)]
+        total: u32,
+        // Accepted on input but kept out of responses
+        #[fastjson(skip_serializing)]
+        secret: Option<String>,
+    }
+
+    let invoice = Invoice { net: 100, tax: 20, total: 120, secret: Some("s".to_string()) };
+    let json = to_string(&invoice).unwrap();
+    assert_eq!(json, r#"{"net": 100, "tax": 20, "total": 120}"#);
+
+    // The written total is ignored and defaults instead
+    let decoded: Invoice = from_str(&json).unwrap();
+    assert_eq!(decoded, Invoice { net: 100, tax: 20, total: 0, secret: None });
+
+    let decoded: Invoice = from_str(r#"{"net": 1, "tax": 2, "secret": "x"}"#).unwrap();
+    assert_eq!(decoded.total, 0);
+    assert_eq!(decoded.secret.as_deref(), Some("x"));
+
+    // Tuple fields follow the same rules, so array lengths differ by direction
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Pair(u8, #[fastjson(skip_deserializing)] u8);
+
+    assert_eq!(to_string(&Pair(1, 2)).unwrap(), "[1, 2]");
+    assert_eq!(from_str::<Pair>("[1]").unwrap(), Pair(1, 0));
+}
+
+#[test]
+fn test_enum_tuple_variant_generic_fields() {
+    use std::collections::HashMap;
+
+    // Commas inside generic arguments must not split the variant's fields
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum E {
+        Pair(HashMap<String, i32>, Vec<(u8, u8)>),
+        Nested(Result<Vec<u8>, String>),
+    }
+
+    let mut counts = HashMap::new();
+    counts.insert("a".to_string(), 1);
+    let pair = E::Pair(counts, vec![(1, 2), (3, 4)]);
+    let json = to_string(&pair).unwrap();
+    assert_eq!(json, r#"{"type": "Pair", "data": [{"a": 1}, [[1, 2], [3, 4]]]}"#);
+    assert_eq!(from_str::<E>(&json).unwrap(), pair);
+
+    let nested = E::Nested(Ok(vec![7]));
+    let json = to_string(&nested).unwrap();
+    assert_eq!(json, r#"{"type": "Nested", "data": [{"Ok": [7]}]}"#);
+    assert_eq!(from_str::<E>(&json).unwrap(), nested);
+
+    // The field count is two, not the number of top-level commas
+    assert!(from_str::<E>(r#"{"type": "Pair", "data": [{}, [], []]}"#).is_err());
+}
+
+#[test]
+fn test_field_order() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Envelope {
+        payload: String,
+        id: u32,
+        #[fastjson(order = 0)]
+        version: u8,
+        #[fastjson(order = 1, rename = "kind")]
+        kind_name: String,
+    }
+
+    let envelope = Envelope { payload: "p".to_string(), id: 7, version: 2, kind_name: "k".to_string() };
+    let json = to_string(&envelope).unwrap();
+    assert_eq!(json, r#"{"version": 2, "kind": "k", "payload": "p", "id": 7}"#);
+    assert_eq!(from_str::<Envelope>(&json).unwrap(), envelope);
+
+    // Applies to struct variants too, after the tag
+    #[derive(Serialize, Debug)]
+    enum Message {
+        Ping { seq: u32, #[fastjson(order = 0)] version: u8 },
+    }
+
+    let json = to_string(&Message::Ping { seq: 1, version: 3 }).unwrap();
+    assert_eq!(json, r#"{"type": "Ping", "version": 3, "seq": 1}"#);
+}
+
+#[test]
+fn test_non_string_map_keys() {
+    use std::collections::HashMap;
+
+    let mut names: HashMap<u32, String> = HashMap::new();
+    names.insert(42, "answer".to_string());
+    names.insert(7, "lucky".to_string());
+
+    let json = to_string(&names).unwrap();
+    let value = fastjson::parse(&json).unwrap();
+    assert_eq!(value.get("42").and_then(|v| v.as_str()), Some("answer"));
+    assert_eq!(from_str::<HashMap<u32, String>>(&json).unwrap(), names);
+
+    let mut flags: HashMap<char, bool> = HashMap::new();
+    flags.insert('x', true);
+    assert_eq!(to_string(&flags).unwrap(), r#"{"x": true}"#);
+    assert_eq!(from_str::<HashMap<char, bool>>(r#"{"x": true}"#).unwrap(), flags);
+
+    // Keys that do not parse as the key type are rejected
+    assert!(from_str::<HashMap<u32, String>>(r#"{"forty": "x"}"#).is_err());
+}
+
+#[test]
+fn test_container_skip_none() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(skip_none)]
+    struct Profile {
+        name: String,
+        nickname: Option<String>,
+        age: Option<u32>,
+        #[fastjson(keep_none)]
+        email: Option<String>,
+    }
+
+    let empty = Profile { name: "a".to_string(), nickname: None, age: None, email: None };
+    let json = to_string(&empty).unwrap();
+    assert_eq!(json, r#"{"name": "a", "email": null}"#);
+    assert_eq!(from_str::<Profile>(&json).unwrap(), empty);
+
+    let full = Profile { name: "a".to_string(), nickname: Some("b".to_string()), age: Some(3), email: None };
+    assert_eq!(to_string(&full).unwrap(), r#"{"name": "a", "nickname": "b", "age": 3, "email": null}"#);
+
+    #[derive(Serialize, Debug)]
+    #[fastjson(skip_none)]
+    enum Event {
+        Login { user: Option<String> },
+    }
+
+    assert_eq!(to_string(&Event::Login { user: None }).unwrap(), r#"{"type": "Login"}"#);
+}
+
+#[test]
+fn test_generic_bounds() {
+    // Type parameters get `T: Serialize` / `T: Deserialize` bounds by default
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Page<T> {
+        items: Vec<T>,
+        next: Option<String>,
+    }
+
+    let page = Page { items: vec![1u8, 2], next: None };
+    let json = to_string(&page).unwrap();
+    assert_eq!(json, r#"{"items": [1, 2], "next": null}"#);
+    assert_eq!(from_str::<Page<u8>>(&json).unwrap(), page);
+
+    // The field type is an associated type, so `B: Serialize` would be wrong:
+    // the backend marker itself is never serialized
+    trait Backend {
+        type Id;
+    }
+    struct Postgres;
+    impl Backend for Postgres {
+        type Id = u64;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[fastjson(bound = "B::Id: fastjson::Serialize + fastjson::Deserialize")]
+    struct Record<B: Backend> {
+        id: B::Id,
+        name: String,
+    }
+
+    let record: Record<Postgres> = Record { id: 7, name: "row".to_string() };
+    let json = to_string(&record).unwrap();
+    assert_eq!(json, r#"{"id": 7, "name": "row"}"#);
+    let decoded: Record<Postgres> = from_str(&json).unwrap();
+    assert_eq!((decoded.id, decoded.name), (7, "row".to_string()));
+
+    // Separate bounds per direction, alongside the type's own where clause
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(bound(serialize = "T: fastjson::Serialize", deserialize = "T: fastjson::Deserialize"))]
+    enum Tree<T = u8>
+    where
+        T: Clone,
+    {
+        Leaf(T),
+        Node { children: Vec<Tree<T>> },
+    }
+
+    let tree: Tree = Tree::Node { children: vec![Tree::Leaf(1), Tree::Leaf(2)] };
+    let json = to_string(&tree).unwrap();
+    assert_eq!(from_str::<Tree>(&json).unwrap(), tree);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Pair<T>(T, T)
+    where
+        T: Copy;
+
+    assert_eq!(to_string(&Pair(1, 2)).unwrap(), "[1, 2]");
+    assert_eq!(from_str::<Pair<i8>>("[-1, 2]").unwrap(), Pair(-1, 2));
+}
+
+#[test]
+fn test_pointer_as() {
+    use fastjson::{parse, Error};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Author {
+        name: String,
+        karma: i32,
+    }
+
+    let response = parse(
+        r#"{"meta": {"total": 2}, "posts": [{"title": "a", "author": {"name": "x", "karma": -3}}, {"title": "b"}]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(response.pointer_as::<u32>("/meta/total").unwrap(), 2);
+    assert_eq!(
+        response.pointer_as::<Author>("/posts/0/author").unwrap(),
+        Author { name: "x".to_string(), karma: -3 }
+    );
+    assert_eq!(response.pointer("/posts/1/title").and_then(|v| v.as_str()), Some("b"));
+
+    // Missing paths and mismatched types both say where they went wrong
+    assert_eq!(
+        response.pointer_as::<Author>("/posts/1/author"),
+        Err(Error::MissingField("/posts/1/author".to_string()))
+    );
+    match response.pointer_as::<u32>("/posts/0/title") {
+        Err(Error::TypeError(msg)) => assert!(msg.ends_with(" at /posts/0/title"), "{}", msg),
+        other => panic!("expected a type error, got {:?}", other),
+    }
+    assert!(response.pointer_as::<u32>("meta/total").is_err());
+}
+
+#[test]
+fn test_unit_variant_object_form() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Status {
+        Active,
+        Suspended { reason: String },
+    }
+
+    let from_string: Status = from_str(r#""Active""#).unwrap();
+    let from_object: Status = from_str(r#"{"type": "Active"}"#).unwrap();
+    assert_eq!(from_string, Status::Active);
+    assert_eq!(from_object, Status::Active);
+
+    // Custom tag keys and renames apply to the object form too
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[fastjson(tag = "kind", rename_all = "snake_case")]
+    enum Shape {
+        EmptySet,
+        Circle { r: u32 },
+    }
+
+    assert_eq!(from_str::<Shape>(r#""empty_set""#).unwrap(), Shape::EmptySet);
+    assert_eq!(from_str::<Shape>(r#"{"kind": "empty_set"}"#).unwrap(), Shape::EmptySet);
+    assert!(from_str::<Shape>(r#"{"type": "empty_set"}"#).is_err());
+}
+
+#[test]
+fn test_uniform_variants() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(uniform_variants, tag = "kind")]
+    enum Status {
+        Active,
+        Suspended { reason: String },
+        Moved(String),
+    }
+
+    let cases = [
+        (Status::Active, r#"{"kind": "Active"}"#),
+        (Status::Suspended { reason: "spam".to_string() }, r#"{"kind": "Suspended", "reason": "spam"}"#),
+        (Status::Moved("x".to_string()), r#"{"kind": "Moved", "data": ["x"]}"#),
+    ];
+    for (status, expected) in cases {
+        let json = to_string(&status).unwrap();
+        assert_eq!(json, expected);
+        assert_eq!(from_str::<Status>(&json).unwrap(), status);
+    }
+
+    // The bare string form is still read
+    assert_eq!(from_str::<Status>(r#""Active""#).unwrap(), Status::Active);
+}
+
+#[test]
+fn test_wrapping() {
+    use std::num::Wrapping;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Counters {
+        sent: Wrapping<u32>,
+        seq: Wrapping<i8>,
+    }
+
+    let mut counters = Counters { sent: Wrapping(u32::MAX), seq: Wrapping(i8::MIN) };
+    let json = to_string(&counters).unwrap();
+    assert_eq!(json, r#"{"sent": 4294967295, "seq": -128}"#);
+    assert_eq!(from_str::<Counters>(&json).unwrap(), counters);
+
+    counters.sent += Wrapping(1);
+    assert_eq!(to_string(&counters.sent).unwrap(), "0");
+    assert!(from_str::<Wrapping<u32>>("4294967296").is_err());
+}
+
+#[test]
+fn test_struct_type_error_is_concise() {
+    use fastjson::{Error, Value};
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Config {
+        name: String,
+    }
+
+    // A large array sent where an object is expected is named, not dumped
+    let big = format!("[{}]", vec!["\"xxxxxxxxxxxxxxxx\""; 100_000].join(", "));
+    let err = from_str::<Config>(&big).unwrap_err();
+    assert_eq!(err, Error::TypeError("expected object for Config, found array".to_string()));
+    assert!(err.to_string().len() < 100);
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Point(i32, i32);
+    let err = from_str::<Point>(r#"{"x": 1}"#).unwrap_err();
+    assert!(err.to_string().len() < 100, "{}", err);
+
+    let mut array = Value::Array(vec![Value::from("x".repeat(10_000))]);
+    let err = array.insert("k", 1).unwrap_err();
+    assert_eq!(err.to_string(), "Type error: cannot insert a key into array");
+}
+
+#[test]
+fn test_tag_ignore_case() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(tag_ignore_case)]
+    enum Color {
+        Red,
+        Custom(String),
+        Named { name: String },
+    }
+
+    assert_eq!(from_str::<Color>(r#""RED""#).unwrap(), Color::Red);
+    assert_eq!(from_str::<Color>(r#"{"type": "red"}"#).unwrap(), Color::Red);
+    assert_eq!(
+        from_str::<Color>(r##"{"type": "CUSTOM", "data": ["#336699"]}"##).unwrap(),
+        Color::Custom("#336699".to_string())
+    );
+    assert_eq!(
+        from_str::<Color>(r#"{"type": "named", "name": "teal"}"#).unwrap(),
+        Color::Named { name: "teal".to_string() }
+    );
+    assert!(from_str::<Color>(r#""Blue""#).is_err());
+
+    // Serialization keeps the declared casing
+    assert_eq!(to_string(&Color::Custom("x".to_string())).unwrap(), r#"{"type": "Custom", "data": ["x"]}"#);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[fastjson(external_tag, tag_ignore_case, rename_all = "snake_case")]
+    enum Event {
+        KeyDown(u32),
+    }
+
+    assert_eq!(from_str::<Event>(r#"{"KEY_DOWN": 13}"#).unwrap(), Event::KeyDown(13));
+}
+
+#[test]
+fn test_tuple_struct_trailing_defaults() {
+    // Version 1 was `Point(i32, i32)`; later versions appended fields
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point(i32, i32, #[fastjson(default)] i32, #[fastjson(default)] Option<String>);
+
+    let point: Point = from_str("[1, 2]").unwrap();
+    assert_eq!(point, Point(1, 2, 0, None));
+    let point: Point = from_str(r#"[1, 2, 3]"#).unwrap();
+    assert_eq!(point, Point(1, 2, 3, None));
+    let point: Point = from_str(r#"[1, 2, 3, "z"]"#).unwrap();
+    assert_eq!(point, Point(1, 2, 3, Some("z".to_string())));
+
+    // Required positions are still required, and extra elements still rejected
+    let err = from_str::<Point>("[1]").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Type error: expected array with 2 to 4 elements for Point, found array with 1 elements"
+    );
+    assert!(from_str::<Point>(r#"[1, 2, 3, "z", 5]"#).is_err());
+
+    // Serializing always writes every position
+    assert_eq!(to_string(&Point(1, 2, 0, None)).unwrap(), "[1, 2, 0, null]");
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Line(u8, #[fastjson(default)] u8),
+    }
+    let shape: Shape = from_str(r#"{"type": "Line", "data": [4]}"#).unwrap();
+    assert_eq!(shape, Shape::Line(4, 0));
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        #[fastjson(default)]
+        retries: u32,
+    }
+    let config: Config = from_str(r#"{"name": "a"}"#).unwrap();
+    assert_eq!(config, Config { name: "a".to_string(), retries: 0 });
+}
+
+#[test]
+fn test_columnar() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Table {
+        #[fastjson(columnar)]
+        rows: Vec<Row>,
+    }
+
+    let json = r#"{"rows": {"name": ["a", "b"], "age": [1, 2]}}"#;
+    let table: Table = from_str(json).unwrap();
+    assert_eq!(
+        table.rows,
+        vec![
+            Row { name: "a".to_string(), age: 1 },
+            Row { name: "b".to_string(), age: 2 },
+        ]
+    );
+    assert_eq!(to_string(&table).unwrap(), json);
+
+    let err = from_str::<Table>(r#"{"rows": {"name": ["a", "b"], "age": [1]}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "Custom error: column `age` has 1 values, expected 2 like column `name`");
+    assert!(from_str::<Table>(r#"{"rows": {"name": "a", "age": 1}}"#).is_err());
+
+    // Rows missing a key get null in that column
+    #[derive(Serialize, Debug)]
+    struct Sparse {
+        #[fastjson(skip_if_none)]
+        note: Option<String>,
+    }
+    let rows = [Sparse { note: None }, Sparse { note: Some("x".to_string()) }, Sparse { note: None }];
+    let value = fastjson::columnar::serialize(&rows).unwrap();
+    assert_eq!(to_string(&value).unwrap(), r#"{"note": [null, "x", null]}"#);
+}
+
+mod validators {
+    pub fn percentage(value: &u8) -> Result<(), String> {
+        if *value <= 100 {
+            Ok(())
+        } else {
+            Err(format!("{} is not a percentage", value))
+        }
+    }
+
+    pub fn non_empty<T: AsRef<str>>(value: &T) -> Result<(), String> {
+        if value.as_ref().is_empty() {
+            Err("must not be empty".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn test_field_validation() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Progress {
+        #[fastjson(validate = "validators::percentage")]
+        done: u8,
+        #[fastjson(rename = "label", validate = "validators::non_empty")]
+        name: String,
+    }
+
+    let progress: Progress = from_str(r#"{"done": 40, "label": "upload"}"#).unwrap();
+    assert_eq!(progress, Progress { done: 40, name: "upload".to_string() });
+
+    let err = from_str::<Progress>(r#"{"done": 140, "label": "upload"}"#).unwrap_err();
+    assert_eq!(err.to_string(), "Custom error: field `done`: 140 is not a percentage");
+    let err = from_str::<Progress>(r#"{"done": 1, "label": ""}"#).unwrap_err();
+    assert_eq!(err.to_string(), "Custom error: field `label`: must not be empty");
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Ratio(#[fastjson(validate = "validators::percentage")] u8);
+    assert_eq!(from_str::<Ratio>("[100]").unwrap(), Ratio(100));
+    assert!(from_str::<Ratio>("[101]").is_err());
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[fastjson(external_tag)]
+    enum Reading {
+        Level(#[fastjson(validate = "validators::percentage")] u8),
+    }
+    assert!(from_str::<Reading>(r#"{"Level": 50}"#).is_ok());
+    assert!(from_str::<Reading>(r#"{"Level": 250}"#).is_err());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_serde_json_conversion() {
+    use fastjson::{parse, Number, Value};
+
+    let text = r#"{"id": 18446744073709551615, "delta": -9223372036854775808, "ratio": 0.25, "ok": true, "tags": ["a", null], "nested": {"empty": {}}}"#;
+    let ours = parse(text).unwrap();
+    let theirs: serde_json::Value = serde_json::from_str(text).unwrap();
+
+    assert_eq!(Value::from(theirs.clone()), ours);
+    assert_eq!(serde_json::Value::from(ours.clone()), theirs);
+
+    // Integers keep their exact type across the conversion
+    let converted = serde_json::Value::from(ours);
+    assert_eq!(converted["id"].as_u64(), Some(u64::MAX));
+    assert_eq!(converted["delta"].as_i64(), Some(i64::MIN));
+    assert!(converted["ratio"].is_f64());
+    let back = Value::from(converted);
+    assert_eq!(back.get("id").unwrap().as_u64(), Some(u64::MAX));
+    assert_eq!(back.get("delta").unwrap().as_i64(), Some(i64::MIN));
+    assert!(back.get("ratio").unwrap().as_number().unwrap().is_f64());
+
+    let half = Number::try_from(serde_json::Number::from_f64(0.5).unwrap()).unwrap();
+    assert!(half.is_f64());
+    assert_eq!(half.as_f64(), 0.5);
+
+    // Non-finite floats never reach a Value, so they stay null on the way out
+    assert_eq!(serde_json::Value::from(Value::from(f64::NAN)), serde_json::Value::Null);
+    assert_eq!(serde_json::Number::from(Number::from_f64(0.5).unwrap()).as_f64(), Some(0.5));
+}
+
+#[test]
+fn test_struct_tag() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[fastjson(tag = "kind", tag_value = "Widget")]
+    struct Widget {
+        id: u32,
+        label: String,
+    }
+
+    let widget = Widget { id: 7, label: "knob".to_string() };
+    let json = to_string(&widget).unwrap();
+    assert_eq!(json, r#"{"kind": "Widget", "id": 7, "label": "knob"}"#);
+    assert_eq!(from_str::<Widget>(&json).unwrap(), widget);
+
+    // The tag may appear anywhere, but must be present and match
+    let reordered: Widget = from_str(r#"{"id": 7, "label": "knob", "kind": "Widget"}"#).unwrap();
+    assert_eq!(reordered, widget);
+    let err = from_str::<Widget>(r#"{"kind": "Gadget", "id": 7, "label": "knob"}"#).unwrap_err();
+    assert_eq!(err.to_string(), r#"Type error: expected "kind" to be "Widget" for Widget, found "Gadget""#);
+    let err = from_str::<Widget>(r#"{"kind": 1, "id": 7, "label": "knob"}"#).unwrap_err();
+    assert_eq!(err.to_string(), r#"Type error: expected "kind" to be "Widget" for Widget, found number"#);
+    let err = from_str::<Widget>(r#"{"id": 7, "label": "knob"}"#).unwrap_err();
+    assert_eq!(err.to_string(), "Missing field: kind");
+}